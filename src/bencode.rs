@@ -1,110 +1,360 @@
-use crate::decode::{decode_dict, decode_int, decode_list, decode_message};
+use crate::bencode_ref::BencodeRef;
+use crate::decode::{DecodeOptions, Decoder};
+use crate::encode::{self, Encoder, Sink};
+use crate::error::{BencodeError, EncodeError, TypeError};
+use crate::escape_u8_slice;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self};
+use core::iter::Peekable;
 use core::panic;
-use std::collections::BTreeMap;
-use std::fmt::{self};
-use std::iter::Peekable;
-use std::slice::Iter;
+use core::slice::Iter;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[cfg(feature = "std")]
+pub use crate::de::from_bytes;
+#[cfg(feature = "std")]
+pub use crate::diff::diff;
+#[cfg(feature = "std")]
+pub use crate::ser::{to_bytes, to_value};
+
+/// Deepest nesting `is_canonical` and `canonicalize` decode. It replaces the default
+/// `DecodeOptions::max_depth` so ordinary documents are never turned away, while still
+/// keeping the recursive decoder off the end of the stack.
+pub const CANONICAL_MAX_DEPTH: usize = 512;
+
+// Integers of any size are canonical, so neither check below should trip over them
+fn canonical_options() -> DecodeOptions {
+    DecodeOptions {
+        big_ints: true,
+        max_depth: CANONICAL_MAX_DEPTH,
+        ..Default::default()
+    }
+}
+
+/// Whether src is exactly one value in BEP 3 canonical form: sorted unique keys, integers and
+/// lengths without padding or negative zero, and nothing after the value. Only canonical
+/// documents survive a decode and `encode_val` byte for byte. Documents nested deeper than
+/// `CANONICAL_MAX_DEPTH` are not checked and count as not canonical.
+pub fn is_canonical(src: &[u8]) -> bool {
+    BencodeRef::decode_with_options(src, canonical_options()).is_ok()
+}
+
+/// Rewrites src in canonical form. Keys are sorted, the first of any duplicate keys is kept
+/// and padded numbers are written as their value. Fails if src is not bencode even leniently,
+/// or with `TooDeep` if it is nested deeper than `CANONICAL_MAX_DEPTH`.
+pub fn canonicalize(src: &[u8]) -> Result<Vec<u8>, BencodeError> {
+    let options = DecodeOptions {
+        lenient: true,
+        ..canonical_options()
+    };
+    Ok(Bencode::decode_with_options(src, options)?.encode_val())
+}
+
 #[derive(PartialEq)]
 pub enum Bencode {
     Message(Vec<u8>),
-    Int(isize),
+    Int(i64),
+    /// Integer outside the i64 range, kept as the digits it was written with (optionally
+    /// led by '-'). Only produced when `DecodeOptions::big_ints` is set, and encoded back
+    /// byte for byte.
+    BigInt(String),
     List(Vec<Bencode>),
     Dict(BTreeMap<Vec<u8>, Bencode>),
+    /// Dictionary entries in the order they were written, duplicates included. Only produced
+    /// when `DecodeOptions::preserve_order` is set. `encode_val` writes it sorted with the
+    /// first of any duplicates kept, `encode_preserving_order` writes it back as it is.
+    OrderedDict(Vec<(Vec<u8>, Bencode)>),
     Stop,
 }
 
 impl Bencode {
     // Convenience method to decode a whole string and return all bencode values in a vec
-    pub fn decode_all(src: &[u8]) -> Result<Vec<Bencode>, std::io::Error> {
-        let mut vals = Vec::<Bencode>::new();
-        let mut it = src.iter().peekable();
-        while it.peek().is_some() {
-            match Self::decode_dispatch(&mut it) {
-                Ok(Bencode::Stop) => break,
-                Ok(x) => vals.push(x),
-                Err(e) => return Err(e),
-            }
-        }
-        Ok(vals)
+    pub fn decode_all(src: &[u8]) -> Result<Vec<Bencode>, BencodeError> {
+        Ok(BencodeRef::decode_all(src)?
+            .into_iter()
+            .map(BencodeRef::into_owned)
+            .collect())
+    }
+
+    // Decodes src as exactly one value, enforcing the limits in options
+    pub fn decode_with_options(
+        src: &[u8],
+        options: DecodeOptions,
+    ) -> Result<Bencode, BencodeError> {
+        Ok(BencodeRef::decode_with_options(src, options)?.into_owned())
     }
 
+    // Lenient decode that normalises the input: keys come out sorted, the first of any
+    // duplicate keys wins and padded numbers are read as their value. Every spec violation
+    // found on the way is returned next to the value.
+    pub fn decode_lenient(src: &[u8]) -> Result<(Bencode, Vec<BencodeError>), BencodeError> {
+        let (val, warnings) = BencodeRef::decode_lenient(src)?;
+        Ok((val.into_owned(), warnings))
+    }
+
+    // Decodes the next value from the iterator and advances it past that value.
+    // Returns Stop (without consuming) if the iterator is empty or sitting on an 'e'.
+    // Error offsets are counted from the iterator's position when this was called.
     pub fn decode_dispatch(
         byte_string: &mut Peekable<Iter<'_, u8>>,
-    ) -> Result<Bencode, std::io::Error> {
-        let mut val = Bencode::Stop;
-        if let Some(ch) = byte_string.peek() {
-            val = match **ch {
-                b'e' => Bencode::Stop,
-                b'i' => {
-                    byte_string.next();
-                    decode_int(byte_string)?
-                }
-                b'0'..=b'9' => decode_message(byte_string)?,
-                b'l' => {
-                    byte_string.next();
-                    decode_list(byte_string, Vec::<Bencode>::new())?
+    ) -> Result<Bencode, BencodeError> {
+        match byte_string.peek() {
+            None | Some(b'e') => return Ok(Bencode::Stop),
+            Some(_) => {}
+        }
+        // A Peekable hides the slice behind it, so the value is copied out for the Decoder.
+        // Only its own bytes are, which keeps decoding value after value linear. Malformed
+        // input gets the rest of the iterator so the error is the one Decoder reports.
+        let len = value_len(byte_string.clone().copied()).unwrap_or(usize::MAX);
+        let bytes: Vec<u8> = byte_string.clone().take(len).copied().collect();
+        let mut decoder = Decoder::new(&bytes);
+        let val = decoder.decode_next()?.into_owned();
+        byte_string.nth(decoder.position() - 1);
+        Ok(val)
+    }
+}
+
+// Length of the value at the front of bytes, found by skipping over it without decoding
+// anything. None if it is malformed or cut short.
+fn value_len(mut bytes: impl Iterator<Item = u8>) -> Option<usize> {
+    let mut len = 0usize;
+    let mut depth = 0usize;
+    loop {
+        len += 1;
+        match bytes.next()? {
+            b'i' => loop {
+                len += 1;
+                if bytes.next()? == b'e' {
+                    break;
                 }
-                b'd' => {
-                    byte_string.next();
-                    decode_dict(byte_string, BTreeMap::<Vec<u8>, Bencode>::new())?
+            },
+            b'l' | b'd' => {
+                depth += 1;
+                continue;
+            }
+            b'e' if depth > 0 => depth -= 1,
+            digit @ b'0'..=b'9' => {
+                let mut n = usize::from(digit - b'0');
+                loop {
+                    len += 1;
+                    match bytes.next()? {
+                        b':' => break,
+                        d @ b'0'..=b'9' => {
+                            n = n.checked_mul(10)?.checked_add(usize::from(d - b'0'))?
+                        }
+                        _ => return None,
+                    }
                 }
-                a => {
-                    let s = format!(
-                        "Strange value in decode_dispatch() found: {a}:\n\nIterator Dump: {:#X?}",
-                        byte_string.collect::<Vec<_>>()
-                    );
-                    let error = std::io::Error::new(std::io::ErrorKind::InvalidData, s);
-                    return Err(error);
+                if n > 0 {
+                    bytes.nth(n - 1)?;
                 }
-            };
+                len = len.checked_add(n)?;
+            }
+            _ => return None,
+        }
+        if depth == 0 {
+            return Some(len);
         }
-        Ok(val)
     }
 }
 
 impl Bencode {
     pub fn encode_val(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(self.encoded_len());
+        // Writing to a Vec can not fail, so the error is a Stop or bad BigInt somewhere in self
+        match self.encode_to(&mut res) {
+            Ok(()) => res,
+            Err(EncodeError::Stop) => panic!("Stop val passed to encode_val."),
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Like `encode_val`, but `OrderedDict`s are written in their own order with duplicates
+    pub fn encode_preserving_order(&self) -> Vec<u8> {
+        let mut res = Vec::with_capacity(encode::encoded_len_preserving_order(self));
+        match Encoder::new(&mut res).preserve_order(true).encode(self) {
+            Ok(()) => res,
+            Err(EncodeError::Stop) => panic!("Stop val passed to encode_preserving_order."),
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Streams the encoded value into w
+    pub fn encode_to<W: Sink>(&self, w: W) -> Result<(), W::Error> {
+        Encoder::new(w).encode(self)
+    }
+
+    /// Size of the encoded value in bytes, computed without encoding it
+    pub fn encoded_len(&self) -> usize {
+        encode::encoded_len(self)
+    }
+}
+
+// Decoding straight from files and readers. Input is always one complete value, anything
+// after it fails with TrailingData. Decode errors come back as InvalidData io errors
+// wrapping the BencodeError, which `get_ref` and `downcast_ref` give access to.
+#[cfg(feature = "std")]
+impl Bencode {
+    pub fn from_reader<R: Read>(r: R) -> io::Result<Bencode> {
+        Self::from_reader_with_options(r, DecodeOptions::default())
+    }
+
+    /// Reading stops one byte past `max_input_len`, so an oversized stream fails without
+    /// being buffered in full
+    pub fn from_reader_with_options<R: Read>(r: R, options: DecodeOptions) -> io::Result<Bencode> {
+        let limit = u64::try_from(options.max_input_len)
+            .unwrap_or(u64::MAX)
+            .saturating_add(1);
+        let mut buf = Vec::new();
+        r.take(limit).read_to_end(&mut buf)?;
+        Ok(Bencode::decode_with_options(&buf, options)?)
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Bencode> {
+        let src = std::fs::read(path)?;
+        Ok(Bencode::decode_with_options(
+            &src,
+            DecodeOptions::default(),
+        )?)
+    }
+
+    /// Like `from_path`, but decodes through a memory map instead of reading the file into
+    /// a buffer first. The map is dropped before returning, the value owns its data.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated (by this or any other process) while it is
+    /// being decoded, see `memmap2::Mmap::map`.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_path_mmap<P: AsRef<Path>>(path: P) -> io::Result<Bencode> {
+        let file = std::fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        Ok(Bencode::decode_with_options(
+            &map,
+            DecodeOptions::default(),
+        )?)
+    }
+}
+
+// Accessors that borrow the payload and return None for any other variant
+impl Bencode {
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Bencode::Message(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// The message as text, None if it is not valid UTF-8
+    pub fn as_str(&self) -> Option<&str> {
+        core::str::from_utf8(self.as_bytes()?).ok()
+    }
+
+    /// None for a `BigInt`, which does not fit in an i64 by construction
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Bencode::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Bencode]> {
+        match self {
+            Bencode::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<Bencode>> {
+        match self {
+            Bencode::List(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Bencode>> {
         match self {
-            Bencode::Int(i) => format!("i{i}e").as_bytes().to_vec(),
-            Bencode::Message(s) => Self::encode_message(s),
-            Bencode::List(l) => Self::encode_list(l),
-            Bencode::Dict(d) => Self::encode_dict(d),
-            Bencode::Stop => panic!("Stop val passed to encode_val."),
+            Bencode::Dict(d) => Some(d),
+            _ => None,
         }
     }
 
-    fn encode_list(v_ref: &Vec<Bencode>) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.push(b'l');
-        for val in v_ref {
-            res.append(&mut val.encode_val())
+    pub fn as_dict_mut(&mut self) -> Option<&mut BTreeMap<Vec<u8>, Bencode>> {
+        match self {
+            Bencode::Dict(d) => Some(d),
+            _ => None,
         }
-        res.push(b'e');
-        res
     }
 
-    fn encode_dict(d_ref: &BTreeMap<Vec<u8>, Bencode>) -> Vec<u8> {
-        let mut res = Vec::new();
-        res.push(b'd');
-        for (k, v) in d_ref.iter() {
-            let mut len = k.len().to_string().as_bytes().to_vec();
-            res.append(&mut len);
-            res.push(b':');
-            res.append(&mut k.clone());
-            res.append(&mut v.encode_val())
+    /// Dictionary entry under key. None if the key is missing or self is not a dictionary.
+    /// In an `OrderedDict` the first entry with the key is returned.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&Bencode> {
+        let key = key.as_ref();
+        match self {
+            Bencode::Dict(d) => d.get(key),
+            Bencode::OrderedDict(d) => d.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
         }
-        res.push(b'e');
-        res
     }
 
-    fn encode_message(s: &Vec<u8>) -> Vec<u8> {
-        let mut s_vec = s.len().to_string().as_bytes().to_vec();
-        s_vec.push(b':');
-        s_vec.append(&mut s.clone());
-        s_vec
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut Bencode> {
+        let key = key.as_ref();
+        match self {
+            Bencode::Dict(d) => d.get_mut(key),
+            Bencode::OrderedDict(d) => d.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Name of the variant, used in error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Bencode::Message(_) => "message",
+            Bencode::Int(_) | Bencode::BigInt(_) => "integer",
+            Bencode::List(_) => "list",
+            Bencode::Dict(_) | Bencode::OrderedDict(_) => "dictionary",
+            Bencode::Stop => "stop",
+        }
+    }
+
+    fn type_error(&self, expected: &'static str) -> TypeError {
+        let found = match self {
+            Bencode::Int(i) => format!("integer {i}"),
+            Bencode::BigInt(s) => format!("integer {s}"),
+            other => other.type_name().to_string(),
+        };
+        TypeError { expected, found }
+    }
+}
+
+impl Bencode {
+    /// Looks up a nested value by a '/' separated path such as `info/files/0/length`.
+    /// Segments index into lists when they are a number and into dictionaries otherwise.
+    /// As in JSON pointers, `~1` stands for '/' and `~0` for '~' inside a key.
+    /// The empty path (or "/") refers to the value itself.
+    pub fn pointer(&self, path: &str) -> Option<&Bencode> {
+        let path = path.strip_prefix('/').unwrap_or(path);
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('/').try_fold(self, |val, segment| {
+            let key = segment.replace("~1", "/").replace("~0", "~");
+            match val {
+                Bencode::List(l) => l.get(key.parse::<usize>().ok()?),
+                _ => val.get(&key),
+            }
+        })
     }
 }
 
+// These panic on the wrong variant. Prefer the as_* accessors for untrusted input.
 impl Bencode {
     pub fn unwrap_message(&self) -> Vec<u8> {
         if let Bencode::Message(s) = self {
@@ -127,7 +377,7 @@ impl Bencode {
         panic!("Called unwrap_list on non list bencode");
     }
 
-    pub fn unwrap_int(&self) -> isize {
+    pub fn unwrap_int(&self) -> i64 {
         if let Bencode::Int(i) = *self {
             return i;
         }
@@ -135,12 +385,260 @@ impl Bencode {
     }
 }
 
+// Integers convert into any integer type they fit in, including from a BigInt
+macro_rules! int_conversions {
+    ($($t:ty),*) => {$(
+        impl TryFrom<&Bencode> for $t {
+            type Error = TypeError;
+
+            fn try_from(val: &Bencode) -> Result<$t, TypeError> {
+                let converted = match val {
+                    Bencode::Int(i) => <$t>::try_from(*i).ok(),
+                    Bencode::BigInt(s) => s.parse::<$t>().ok(),
+                    _ => None,
+                };
+                converted.ok_or_else(|| val.type_error(stringify!($t)))
+            }
+        }
+    )*};
+}
+
+int_conversions!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// 0 and 1, the way bencode stores flags such as `private`
+impl TryFrom<&Bencode> for bool {
+    type Error = TypeError;
+
+    fn try_from(val: &Bencode) -> Result<bool, TypeError> {
+        match val {
+            Bencode::Int(0) => Ok(false),
+            Bencode::Int(1) => Ok(true),
+            _ => Err(val.type_error("0 or 1")),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a Bencode> for &'a [u8] {
+    type Error = TypeError;
+
+    fn try_from(val: &'a Bencode) -> Result<&'a [u8], TypeError> {
+        val.as_bytes().ok_or_else(|| val.type_error("message"))
+    }
+}
+
+impl<'a> TryFrom<&'a Bencode> for &'a str {
+    type Error = TypeError;
+
+    fn try_from(val: &'a Bencode) -> Result<&'a str, TypeError> {
+        val.as_str().ok_or_else(|| val.type_error("utf-8 message"))
+    }
+}
+
+impl<'a> TryFrom<&'a Bencode> for &'a [Bencode] {
+    type Error = TypeError;
+
+    fn try_from(val: &'a Bencode) -> Result<&'a [Bencode], TypeError> {
+        val.as_list().ok_or_else(|| val.type_error("list"))
+    }
+}
+
+impl<'a> TryFrom<&'a Bencode> for &'a BTreeMap<Vec<u8>, Bencode> {
+    type Error = TypeError;
+
+    fn try_from(val: &'a Bencode) -> Result<&'a BTreeMap<Vec<u8>, Bencode>, TypeError> {
+        val.as_dict().ok_or_else(|| val.type_error("dictionary"))
+    }
+}
+
+// Owned conversions move the payload out instead of cloning it
+impl TryFrom<Bencode> for Vec<u8> {
+    type Error = TypeError;
+
+    fn try_from(val: Bencode) -> Result<Vec<u8>, TypeError> {
+        match val {
+            Bencode::Message(m) => Ok(m),
+            other => Err(other.type_error("message")),
+        }
+    }
+}
+
+impl TryFrom<Bencode> for String {
+    type Error = TypeError;
+
+    fn try_from(val: Bencode) -> Result<String, TypeError> {
+        match val {
+            Bencode::Message(m) => String::from_utf8(m).map_err(|e| TypeError {
+                expected: "utf-8 message",
+                found: format!("message '{}'", escape_u8_slice(e.as_bytes())),
+            }),
+            other => Err(other.type_error("utf-8 message")),
+        }
+    }
+}
+
+impl TryFrom<Bencode> for Vec<Bencode> {
+    type Error = TypeError;
+
+    fn try_from(val: Bencode) -> Result<Vec<Bencode>, TypeError> {
+        match val {
+            Bencode::List(l) => Ok(l),
+            other => Err(other.type_error("list")),
+        }
+    }
+}
+
+impl TryFrom<Bencode> for BTreeMap<Vec<u8>, Bencode> {
+    type Error = TypeError;
+
+    fn try_from(val: Bencode) -> Result<BTreeMap<Vec<u8>, Bencode>, TypeError> {
+        match val {
+            Bencode::Dict(d) => Ok(d),
+            other => Err(other.type_error("dictionary")),
+        }
+    }
+}
+
+impl From<&str> for Bencode {
+    fn from(s: &str) -> Bencode {
+        Bencode::Message(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Bencode {
+    fn from(s: String) -> Bencode {
+        Bencode::Message(s.into_bytes())
+    }
+}
+
+impl From<&[u8]> for Bencode {
+    fn from(m: &[u8]) -> Bencode {
+        Bencode::Message(m.to_vec())
+    }
+}
+
+// Byte string literals such as b"peers"
+impl<const N: usize> From<&[u8; N]> for Bencode {
+    fn from(m: &[u8; N]) -> Bencode {
+        Bencode::Message(m.to_vec())
+    }
+}
+
+macro_rules! from_small_int {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Bencode {
+            fn from(i: $t) -> Bencode {
+                Bencode::Int(i64::from(i))
+            }
+        }
+    )*};
+}
+
+from_small_int!(i8, i16, i32, i64, u8, u16, u32);
+
+// Types that can hold values past i64 turn those into a BigInt
+macro_rules! from_wide_int {
+    ($($t:ty),*) => {$(
+        impl From<$t> for Bencode {
+            fn from(i: $t) -> Bencode {
+                match i64::try_from(i) {
+                    Ok(i) => Bencode::Int(i),
+                    Err(_) => Bencode::BigInt(i.to_string()),
+                }
+            }
+        }
+    )*};
+}
+
+from_wide_int!(i128, isize, u64, u128, usize);
+
+// A Vec<u8> becomes a list of integers like any other Vec. Use `&[u8]` for a message.
+impl<T: Into<Bencode>> From<Vec<T>> for Bencode {
+    fn from(l: Vec<T>) -> Bencode {
+        Bencode::List(l.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<K: Into<Vec<u8>>, V: Into<Bencode>> From<BTreeMap<K, V>> for Bencode {
+    fn from(d: BTreeMap<K, V>) -> Bencode {
+        Bencode::Dict(d.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Into<Vec<u8>>, V: Into<Bencode>> From<HashMap<K, V>> for Bencode {
+    fn from(d: HashMap<K, V>) -> Bencode {
+        Bencode::Dict(d.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+}
+
+/// Builds a `Bencode` value. `{ key => value, .. }` is a dictionary, `[ value, .. ]` a list,
+/// and anything else goes through `Bencode::from`. Keys can be anything that converts into
+/// `Vec<u8>` (`&str`, `String`, byte string literals). The outer braces of a dictionary can
+/// be left off.
+#[macro_export]
+macro_rules! bencode {
+    (@dict $map:ident) => {};
+    (@dict $map:ident $key:expr => { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $map.insert($crate::__private::Vec::<u8>::from($key), $crate::bencode!({ $($inner)* }));
+        $crate::bencode!(@dict $map $($($rest)*)?);
+    };
+    (@dict $map:ident $key:expr => [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $map.insert($crate::__private::Vec::<u8>::from($key), $crate::bencode!([ $($inner)* ]));
+        $crate::bencode!(@dict $map $($($rest)*)?);
+    };
+    (@dict $map:ident $key:expr => $val:expr $(, $($rest:tt)*)?) => {
+        $map.insert(
+            $crate::__private::Vec::<u8>::from($key),
+            $crate::bencode::Bencode::from($val),
+        );
+        $crate::bencode!(@dict $map $($($rest)*)?);
+    };
+
+    (@list $list:ident) => {};
+    (@list $list:ident { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $list.push($crate::bencode!({ $($inner)* }));
+        $crate::bencode!(@list $list $($($rest)*)?);
+    };
+    (@list $list:ident [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $list.push($crate::bencode!([ $($inner)* ]));
+        $crate::bencode!(@list $list $($($rest)*)?);
+    };
+    (@list $list:ident $val:expr $(, $($rest:tt)*)?) => {
+        $list.push($crate::bencode::Bencode::from($val));
+        $crate::bencode!(@list $list $($($rest)*)?);
+    };
+
+    ({ $($tt:tt)* }) => {{
+        #[allow(unused_mut)]
+        let mut map = $crate::__private::BTreeMap::new();
+        $crate::bencode!(@dict map $($tt)*);
+        $crate::bencode::Bencode::Dict(map)
+    }};
+    ([ $($tt:tt)* ]) => {{
+        #[allow(unused_mut)]
+        let mut list = $crate::__private::Vec::new();
+        $crate::bencode!(@list list $($tt)*);
+        $crate::bencode::Bencode::List(list)
+    }};
+    () => {
+        $crate::bencode::Bencode::Dict($crate::__private::BTreeMap::new())
+    };
+    ($key:expr => $($rest:tt)*) => {
+        $crate::bencode!({ $key => $($rest)* })
+    };
+    ($val:expr) => {
+        $crate::bencode::Bencode::from($val)
+    };
+}
+
 impl Clone for Bencode {
     fn clone(&self) -> Self {
         match self {
             Bencode::Dict(d) => Bencode::Dict(d.clone()),
+            Bencode::OrderedDict(d) => Bencode::OrderedDict(d.clone()),
             Bencode::List(l) => Bencode::List(l.clone()),
             Bencode::Int(i) => Bencode::Int(*i),
+            Bencode::BigInt(s) => Bencode::BigInt(s.clone()),
             Bencode::Message(v) => Bencode::Message(v.clone()),
             Bencode::Stop => Bencode::Stop,
         }
@@ -148,7 +646,7 @@ impl Clone for Bencode {
 }
 
 impl fmt::Debug for Bencode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Bencode::Dict(d) => {
                 write!(f, "Dict")?;
@@ -156,9 +654,18 @@ impl fmt::Debug for Bencode {
                     .entries(d.iter().map(|(k, v)| (String::from_utf8_lossy(k), v)))
                     .finish()
             }
+            Bencode::OrderedDict(d) => {
+                write!(f, "OrderedDict")?;
+                f.debug_map()
+                    .entries(d.iter().map(|(k, v)| (String::from_utf8_lossy(k), v)))
+                    .finish()
+            }
             Bencode::Int(i) => {
                 write!(f, "Int({})", i)
             }
+            Bencode::BigInt(s) => {
+                write!(f, "BigInt({})", s)
+            }
             Bencode::List(l) => {
                 writeln!(f, "List")?;
                 f.debug_list().entries(l.iter()).finish()
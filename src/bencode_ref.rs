@@ -0,0 +1,130 @@
+use crate::bencode::Bencode;
+use crate::decode::{DecodeOptions, Decoder};
+use crate::error::BencodeError;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self};
+
+/// Borrowed counterpart of `Bencode`. Messages and dictionary keys are slices of the
+/// buffer that was decoded, so large values such as `pieces` are never copied.
+#[derive(PartialEq, Clone)]
+pub enum BencodeRef<'a> {
+    Message(&'a [u8]),
+    Int(i64),
+    /// Digits of an integer that does not fit in an i64, see `Bencode::BigInt`
+    BigInt(&'a str),
+    List(Vec<BencodeRef<'a>>),
+    Dict(BTreeMap<&'a [u8], BencodeRef<'a>>),
+    /// Entries in input order, see `Bencode::OrderedDict`
+    OrderedDict(Vec<(&'a [u8], BencodeRef<'a>)>),
+}
+
+impl<'a> BencodeRef<'a> {
+    // Decodes src as exactly one value. Anything after that value is an error.
+    pub fn decode(src: &'a [u8]) -> Result<BencodeRef<'a>, BencodeError> {
+        Self::decode_with_options(src, DecodeOptions::default())
+    }
+
+    // Same as decode, but with limits suited to untrusted input
+    pub fn decode_with_options(
+        src: &'a [u8],
+        options: DecodeOptions,
+    ) -> Result<BencodeRef<'a>, BencodeError> {
+        let mut decoder = Decoder::with_options(src, options);
+        let val = decoder.decode_next()?;
+        decoder.finish()?;
+        Ok(val)
+    }
+
+    // Decodes src as exactly one value in lenient mode. Returns the value along with every
+    // spec violation (unsorted or duplicate keys, padded numbers) that was let through.
+    pub fn decode_lenient(
+        src: &'a [u8],
+    ) -> Result<(BencodeRef<'a>, Vec<BencodeError>), BencodeError> {
+        let options = DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut decoder = Decoder::with_options(src, options);
+        let val = decoder.decode_next()?;
+        decoder.finish()?;
+        Ok((val, decoder.take_warnings()))
+    }
+
+    // Convenience method to decode a whole string and return all bencode values in a vec
+    pub fn decode_all(src: &'a [u8]) -> Result<Vec<BencodeRef<'a>>, BencodeError> {
+        let mut vals = Vec::<BencodeRef>::new();
+        let mut decoder = Decoder::new(src);
+        while let Some(ch) = decoder.peek() {
+            if ch == b'e' {
+                break;
+            }
+            vals.push(decoder.decode_next()?);
+        }
+        Ok(vals)
+    }
+
+    /// Copies every borrowed slice into an owned `Bencode`.
+    pub fn into_owned(self) -> Bencode {
+        match self {
+            BencodeRef::Message(m) => Bencode::Message(m.to_vec()),
+            BencodeRef::Int(i) => Bencode::Int(i),
+            BencodeRef::BigInt(s) => Bencode::BigInt(strip_padding(s)),
+            BencodeRef::List(l) => {
+                Bencode::List(l.into_iter().map(BencodeRef::into_owned).collect())
+            }
+            BencodeRef::Dict(d) => Bencode::Dict(
+                d.into_iter()
+                    .map(|(k, v)| (k.to_vec(), v.into_owned()))
+                    .collect(),
+            ),
+            BencodeRef::OrderedDict(d) => Bencode::OrderedDict(
+                d.into_iter()
+                    .map(|(k, v)| (k.to_vec(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Lenient decoding borrows padded digits as they are, the owned value is always canonical
+fn strip_padding(digits: &str) -> String {
+    match digits.strip_prefix('-') {
+        Some(d) => format!("-{}", d.trim_start_matches('0')),
+        None => digits.trim_start_matches('0').to_string(),
+    }
+}
+
+impl fmt::Debug for BencodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BencodeRef::Dict(d) => {
+                write!(f, "Dict")?;
+                f.debug_map()
+                    .entries(d.iter().map(|(k, v)| (String::from_utf8_lossy(k), v)))
+                    .finish()
+            }
+            BencodeRef::OrderedDict(d) => {
+                write!(f, "OrderedDict")?;
+                f.debug_map()
+                    .entries(d.iter().map(|(k, v)| (String::from_utf8_lossy(k), v)))
+                    .finish()
+            }
+            BencodeRef::Int(i) => {
+                write!(f, "Int({})", i)
+            }
+            BencodeRef::BigInt(s) => {
+                write!(f, "BigInt({})", s)
+            }
+            BencodeRef::List(l) => {
+                writeln!(f, "List")?;
+                f.debug_list().entries(l.iter()).finish()
+            }
+            BencodeRef::Message(s) => {
+                write!(f, "Message({})", String::from_utf8_lossy(s))
+            }
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::bencode::Bencode;
+use crate::make_bad_data_err;
+
+/// Builds a v1 .torrent for a file or a directory. Every setting except the path is
+/// optional, e.g.
+/// `TorrentBuilder::new("dist").tracker("http://tracker.example/announce").private(true).build()`
+#[derive(Debug, Clone)]
+pub struct TorrentBuilder {
+    path: PathBuf,
+    piece_length: Option<i64>,
+    tiers: Vec<Vec<String>>,
+    web_seeds: Vec<String>,
+    comment: Option<String>,
+    private: bool,
+    creation_date: Option<i64>,
+}
+
+// Automatic piece lengths aim for about this many pieces, within the bounds below
+const TARGET_PIECES: u64 = 1500;
+const MIN_PIECE_LENGTH: i64 = 16 * 1024;
+const MAX_PIECE_LENGTH: i64 = 16 * 1024 * 1024;
+
+// A file of the torrent, by its path on disk and its path inside the torrent
+struct SourceFile {
+    disk_path: PathBuf,
+    path: Vec<String>,
+}
+
+impl TorrentBuilder {
+    pub fn new<P: Into<PathBuf>>(path: P) -> TorrentBuilder {
+        TorrentBuilder {
+            path: path.into(),
+            piece_length: None,
+            tiers: Vec::new(),
+            web_seeds: Vec::new(),
+            comment: None,
+            private: false,
+            creation_date: Some(now()),
+        }
+    }
+
+    /// Bytes per piece, a power of two of at least 16 KiB. When not set it is picked from
+    /// the total size.
+    pub fn piece_length(mut self, piece_length: i64) -> TorrentBuilder {
+        self.piece_length = Some(piece_length);
+        self
+    }
+
+    /// Adds a tracker in a tier of its own, after the tiers added so far
+    pub fn tracker<S: Into<String>>(self, url: S) -> TorrentBuilder {
+        self.tier(vec![url.into()])
+    }
+
+    /// Adds a BEP 12 tier of trackers that clients treat as interchangeable
+    pub fn tier(mut self, urls: Vec<String>) -> TorrentBuilder {
+        if !urls.is_empty() {
+            self.tiers.push(urls);
+        }
+        self
+    }
+
+    /// Adds a BEP 19 web seed url
+    pub fn web_seed<S: Into<String>>(mut self, url: S) -> TorrentBuilder {
+        self.web_seeds.push(url.into());
+        self
+    }
+
+    pub fn comment<S: Into<String>>(mut self, comment: S) -> TorrentBuilder {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Private torrents (BEP 27) only get peers from their trackers
+    pub fn private(mut self, private: bool) -> TorrentBuilder {
+        self.private = private;
+        self
+    }
+
+    /// Seconds since the unix epoch. Defaults to now, None leaves the date out, which makes
+    /// the output the same every time for the same files.
+    pub fn creation_date(mut self, date: Option<i64>) -> TorrentBuilder {
+        self.creation_date = date;
+        self
+    }
+
+    /// Reads and hashes the files and returns the metainfo dictionary
+    pub fn build(&self) -> io::Result<Bencode> {
+        let name = self
+            .path
+            .file_name()
+            .ok_or_else(|| make_bad_data_err("Path has no file name"))?;
+        let name = utf8_name(name)?;
+        let metadata = fs::metadata(&self.path)?;
+        let files = match metadata.is_dir() {
+            true => {
+                let mut files = Vec::new();
+                collect_files(&self.path, &mut Vec::new(), &mut files)?;
+                if files.is_empty() {
+                    return Err(make_bad_data_err("Directory has no files"));
+                }
+                files
+            }
+            false => vec![SourceFile {
+                disk_path: self.path.clone(),
+                path: vec![name.clone()],
+            }],
+        };
+        let total: u64 = files
+            .iter()
+            .map(|f| fs::metadata(&f.disk_path).map(|m| m.len()))
+            .sum::<io::Result<u64>>()?;
+        let piece_length = match self.piece_length {
+            Some(len) if len >= MIN_PIECE_LENGTH && len.count_ones() == 1 => len,
+            Some(_) => {
+                return Err(make_bad_data_err(
+                    "Piece length is not a power of two of at least 16 KiB",
+                ))
+            }
+            None => auto_piece_length(total),
+        };
+
+        let mut hasher = PieceHasher::new(piece_length as usize);
+        let mut lengths = Vec::with_capacity(files.len());
+        for file in &files {
+            lengths.push(hasher.hash_file(&file.disk_path)?);
+        }
+        let mut info = BTreeMap::new();
+        info.insert(b"name".to_vec(), Bencode::from(name));
+        info.insert(b"piece length".to_vec(), Bencode::Int(piece_length));
+        info.insert(b"pieces".to_vec(), Bencode::Message(hasher.finish()));
+        if self.private {
+            info.insert(b"private".to_vec(), Bencode::Int(1));
+        }
+        if metadata.is_dir() {
+            let entries = files
+                .into_iter()
+                .zip(lengths)
+                .map(|(file, length)| {
+                    Bencode::from(BTreeMap::from([
+                        ("length", Bencode::Int(length)),
+                        ("path", Bencode::from(file.path)),
+                    ]))
+                })
+                .collect();
+            info.insert(b"files".to_vec(), Bencode::List(entries));
+        } else {
+            info.insert(b"length".to_vec(), Bencode::Int(lengths[0]));
+        }
+        Ok(self.metainfo(Bencode::Dict(info)))
+    }
+
+    /// `build` followed by `Bencode::encode_val`, ready to be written to a .torrent file
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        Ok(self.build()?.encode_val())
+    }
+
+    // Wraps the info dict with the keys outside of it
+    fn metainfo(&self, info: Bencode) -> Bencode {
+        let mut root = BTreeMap::new();
+        root.insert(b"info".to_vec(), info);
+        if let Some(announce) = self.tiers.first().and_then(|tier| tier.first()) {
+            root.insert(b"announce".to_vec(), Bencode::from(announce.as_str()));
+        }
+        // announce is enough for a lone tracker
+        if self.tiers.iter().map(Vec::len).sum::<usize>() > 1 {
+            root.insert(b"announce-list".to_vec(), Bencode::from(self.tiers.clone()));
+        }
+        if !self.web_seeds.is_empty() {
+            root.insert(b"url-list".to_vec(), Bencode::from(self.web_seeds.clone()));
+        }
+        if let Some(comment) = &self.comment {
+            root.insert(b"comment".to_vec(), Bencode::from(comment.as_str()));
+        }
+        if let Some(date) = self.creation_date {
+            root.insert(b"creation date".to_vec(), Bencode::Int(date));
+        }
+        root.insert(
+            b"created by".to_vec(),
+            Bencode::from(concat!("bit_tor/", env!("CARGO_PKG_VERSION"))),
+        );
+        Bencode::Dict(root)
+    }
+}
+
+// Smallest power of two that keeps the piece count near TARGET_PIECES
+fn auto_piece_length(total: u64) -> i64 {
+    let wanted = total.div_ceil(TARGET_PIECES).next_power_of_two();
+    (wanted as i64).clamp(MIN_PIECE_LENGTH, MAX_PIECE_LENGTH)
+}
+
+// Files under dir in path order, so the same directory always gives the same torrent
+fn collect_files(dir: &Path, path: &mut Vec<String>, out: &mut Vec<SourceFile>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        path.push(utf8_name(&entry.file_name())?);
+        let disk_path = entry.path();
+        if fs::metadata(&disk_path)?.is_dir() {
+            collect_files(&disk_path, path, out)?;
+        } else {
+            out.push(SourceFile {
+                disk_path,
+                path: path.clone(),
+            });
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+fn utf8_name(name: &std::ffi::OsStr) -> io::Result<String> {
+    name.to_str()
+        .map(str::to_string)
+        .ok_or_else(|| make_bad_data_err(&format!("{name:?} is not valid UTF-8")))
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+// SHA-1s the concatenation of every file fed to it, one piece at a time. Pieces run across
+// file boundaries, only the last one can be short.
+struct PieceHasher {
+    piece: Vec<u8>,
+    piece_length: usize,
+    hashes: Vec<u8>,
+}
+
+impl PieceHasher {
+    fn new(piece_length: usize) -> PieceHasher {
+        PieceHasher {
+            piece: Vec::with_capacity(piece_length),
+            piece_length,
+            hashes: Vec::new(),
+        }
+    }
+
+    // Returns the number of bytes read, which is what goes in the torrent even if the file
+    // changed size since it was listed
+    fn hash_file(&mut self, path: &Path) -> io::Result<i64> {
+        let mut file = File::open(path)?;
+        let mut buf = vec![0; 64 * 1024];
+        let mut len = 0;
+        loop {
+            let read = match file.read(&mut buf) {
+                Ok(0) => return Ok(len),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.update(&buf[..read]);
+            len += read as i64;
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = data.len().min(self.piece_length - self.piece.len());
+            self.piece.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.piece.len() == self.piece_length {
+                self.finish_piece();
+            }
+        }
+    }
+
+    fn finish_piece(&mut self) {
+        let hash = sha1_smol::Sha1::from(&self.piece).digest().bytes();
+        self.hashes.extend(hash);
+        self.piece.clear();
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if !self.piece.is_empty() {
+            self.finish_piece();
+        }
+        self.hashes
+    }
+}
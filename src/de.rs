@@ -0,0 +1,228 @@
+use serde::de::{self, Deserialize, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::bencode_ref::BencodeRef;
+use crate::decode::DecodeOptions;
+use crate::error::SerdeError;
+
+/// Deserializes a `T` from a complete bencoded document. Strings and byte slices in `T`
+/// may borrow from `src`.
+pub fn from_bytes<'de, T: Deserialize<'de>>(src: &'de [u8]) -> Result<T, SerdeError> {
+    // Integers past i64 are let through here, the target type decides if they fit
+    let options = DecodeOptions {
+        big_ints: true,
+        ..Default::default()
+    };
+    T::deserialize(BencodeRef::decode_with_options(src, options)?)
+}
+
+fn unexpected<'a>(val: &'a BencodeRef) -> de::Unexpected<'a> {
+    match val {
+        BencodeRef::Message(m) => de::Unexpected::Bytes(m),
+        BencodeRef::Int(i) => de::Unexpected::Signed(*i),
+        BencodeRef::BigInt(s) => de::Unexpected::Other(s),
+        BencodeRef::List(_) => de::Unexpected::Seq,
+        BencodeRef::Dict(_) | BencodeRef::OrderedDict(_) => de::Unexpected::Map,
+    }
+}
+
+impl<'de> de::Deserializer<'de> for BencodeRef<'de> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            BencodeRef::Message(m) => match std::str::from_utf8(m) {
+                Ok(s) => visitor.visit_borrowed_str(s),
+                Err(_) => visitor.visit_borrowed_bytes(m),
+            },
+            BencodeRef::Int(i) => visitor.visit_i64(i),
+            BencodeRef::BigInt(s) => big_int(s, visitor),
+            BencodeRef::List(l) => {
+                visitor.visit_seq(de::value::SeqDeserializer::new(l.into_iter()))
+            }
+            BencodeRef::Dict(d) => visitor.visit_map(de::value::MapDeserializer::new(
+                d.into_iter().map(|(k, v)| (BencodeRef::Message(k), v)),
+            )),
+            BencodeRef::OrderedDict(d) => visitor.visit_map(de::value::MapDeserializer::new(
+                d.into_iter().map(|(k, v)| (BencodeRef::Message(k), v)),
+            )),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            BencodeRef::Int(0) => visitor.visit_bool(false),
+            BencodeRef::Int(1) => visitor.visit_bool(true),
+            other => Err(de::Error::invalid_type(unexpected(&other), &"0 or 1")),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            BencodeRef::Message(m) => match std::str::from_utf8(m) {
+                Ok(s) => visitor.visit_borrowed_str(s),
+                Err(_) => Err(de::Error::invalid_value(
+                    de::Unexpected::Bytes(m),
+                    &"a utf-8 string",
+                )),
+            },
+            BencodeRef::BigInt(s) => visitor.visit_borrowed_str(s),
+            other => Err(de::Error::invalid_type(unexpected(&other), &"a string")),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self {
+            BencodeRef::Message(m) => visitor.visit_borrowed_bytes(m),
+            other => Err(de::Error::invalid_type(
+                unexpected(&other),
+                &"a byte string",
+            )),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    // Absent keys are handled by serde itself, so any value that is present is Some
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self {
+            BencodeRef::Message(m) => visitor.visit_enum(unit_variant(m)?),
+            BencodeRef::Dict(d) if d.len() == 1 => {
+                let (variant, val) = d.into_iter().next().unwrap();
+                visitor.visit_enum(EnumAccess { variant, val })
+            }
+            BencodeRef::OrderedDict(d) if d.len() == 1 => {
+                let (variant, val) = d.into_iter().next().unwrap();
+                visitor.visit_enum(EnumAccess { variant, val })
+            }
+            other => Err(de::Error::invalid_type(
+                unexpected(&other),
+                &"a variant name or a single entry dictionary",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+// Hands a BigInt to the visitor as the smallest type that holds it. Integers beyond
+// 128 bits can still be read into a String.
+fn big_int<'de, V: Visitor<'de>>(s: &'de str, visitor: V) -> Result<V::Value, SerdeError> {
+    if let Ok(u) = s.parse::<u64>() {
+        visitor.visit_u64(u)
+    } else if let Ok(i) = s.parse::<i128>() {
+        visitor.visit_i128(i)
+    } else if let Ok(u) = s.parse::<u128>() {
+        visitor.visit_u128(u)
+    } else {
+        visitor.visit_borrowed_str(s)
+    }
+}
+
+fn unit_variant(
+    m: &[u8],
+) -> Result<de::value::BorrowedStrDeserializer<'_, SerdeError>, SerdeError> {
+    match std::str::from_utf8(m) {
+        Ok(s) => Ok(de::value::BorrowedStrDeserializer::new(s)),
+        Err(_) => Err(de::Error::invalid_value(
+            de::Unexpected::Bytes(m),
+            &"a utf-8 variant name",
+        )),
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SerdeError> for BencodeRef<'de> {
+    type Deserializer = BencodeRef<'de>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+// Enum encoded as { variant name: contents }
+struct EnumAccess<'de> {
+    variant: &'de [u8],
+    val: BencodeRef<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = SerdeError;
+    type Variant = BencodeRef<'de>;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, BencodeRef<'de>), SerdeError> {
+        let variant = seed.deserialize(BencodeRef::Message(self.variant))?;
+        Ok((variant, self.val))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for BencodeRef<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
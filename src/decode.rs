@@ -1,198 +1,472 @@
-use once_cell::sync::Lazy;
-use regex::bytes::Regex;
-
-use crate::bencode::Bencode;
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
-use std::iter::Peekable;
-use std::slice::Iter;
-
-use crate::{escape_u8_slice, make_bad_data_err};
-
-///Assumes iterator starts at the number (i.e. caller consumed the 'i')
-///Errors
-///     1) No terminal 'e' within iterator
-///     2) String can not be parsed as an int (excluding e)
-///     3) Leading Zero/es
-///     4) Negative Zero
-pub fn decode_int(byte_string: &mut Peekable<Iter<'_, u8>>) -> Result<Bencode, std::io::Error> {
-    let mut relevant_bytes: Vec<u8> = Vec::new();
-    while byte_string.peek().is_some() {
-        let ch = byte_string.next().unwrap();
-        relevant_bytes.push(*ch);
-        if *ch == b'e' {
-            break;
-        }
-    }
-    validate_and_parse_int(relevant_bytes)
+use crate::bencode_ref::BencodeRef;
+use crate::error::{BencodeError, BencodeErrorKind, KeyPath, PathSegment};
+use crate::visit::Visitor;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Limits applied while decoding, so untrusted input (tracker responses, peer supplied
+/// metadata) can not blow the stack or make the decoder hold on to huge amounts of memory.
+/// Exceeding any of them fails the decode with the matching `BencodeErrorKind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Deepest nesting of lists and dictionaries allowed. 0 only allows integers and strings.
+    pub max_depth: usize,
+    /// Longest message, going by its length prefix
+    pub max_string_len: usize,
+    /// Most values (integers, strings, lists and dictionaries all count) in one document
+    pub max_items: usize,
+    /// Largest input accepted, in bytes
+    pub max_input_len: usize,
+    /// Accept unsorted or duplicate dictionary keys and zero padded or negative zero numbers.
+    /// Each violation is recorded as a warning instead of failing the decode.
+    pub lenient: bool,
+    /// Record the byte range every value was decoded from, see `Decoder::spans`
+    pub record_spans: bool,
+    /// Decode integers too large for an i64 as `BigInt` instead of failing with `IntOverflow`
+    pub big_ints: bool,
+    /// Decode dictionaries as `OrderedDict`s, keeping their entries in input order with
+    /// duplicates included. Only useful together with `lenient`, as strict decoding rejects
+    /// anything that is not already sorted.
+    pub preserve_order: bool,
 }
 
-fn validate_and_parse_int(bytes: Vec<u8>) -> Result<Bencode, std::io::Error> {
-    // once_cell::Lazy allows this regex expression to only need compilation once (on first use)
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(-?[1-9][0-9]*)e$|^(0)e$").unwrap());
-    let Some(captures) = RE.captures(&bytes) else {
-        let err_msg = format!(
-            "Malformed integer value passed to decoder: {}",
-            escape_u8_slice(&bytes)
-        );
-        return Err(make_bad_data_err(&err_msg));
-    };
-    let captured_str = std::str::from_utf8(&captures[1]).unwrap();
-    let num = match str::parse::<isize>(captured_str) {
-        Ok(num) => num,
-        Err(_) => {
-            let err_msg = format!("Error parsing int passed to decoder: {captured_str}");
-            return Err(make_bad_data_err(&err_msg));
+impl Default for DecodeOptions {
+    // Only depth is limited by default, to keep deeply nested input from overflowing the stack
+    fn default() -> Self {
+        DecodeOptions {
+            max_depth: 256,
+            max_string_len: usize::MAX,
+            max_items: usize::MAX,
+            max_input_len: usize::MAX,
+            lenient: false,
+            record_spans: false,
+            big_ints: false,
+            preserve_order: false,
         }
-    };
-    Ok(Bencode::Int(num))
+    }
+}
+
+/// Cursor over a bencoded byte slice. Values are decoded as `BencodeRef`s which borrow
+/// their strings straight out of `src`, so nothing is copied while walking the input.
+pub struct Decoder<'a> {
+    src: &'a [u8],
+    pos: usize,
+    // Keys and indexes leading to the value currently being decoded, used for error reporting
+    path: Vec<PathStep<'a>>,
+    options: DecodeOptions,
+    depth: usize,
+    items: usize,
+    // Spec violations let through in lenient mode
+    warnings: Vec<BencodeError>,
+    spans: Spans,
+}
+
+/// Where in the input each decoded value came from, keyed by its path in the document.
+/// Slicing the input with a span gives back the exact bytes of that value, which is what
+/// has to be hashed for an info hash, even if the value was not canonically encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Spans(pub BTreeMap<KeyPath, Range<usize>>);
+
+impl Spans {
+    pub fn get(&self, path: &KeyPath) -> Option<Range<usize>> {
+        self.0.get(path).cloned()
+    }
+}
+
+enum PathStep<'a> {
+    Key(&'a [u8]),
+    Index(usize),
 }
 
-pub fn decode_message(byte_string: &mut Peekable<Iter<'_, u8>>) -> Result<Bencode, std::io::Error> {
-    let number_bytes = byte_string.take_while(|c| **c != b':').copied().collect();
-    let number_str = match String::from_utf8(number_bytes) {
-        Ok(s) => s,
-        Err(_) => {
-            return Err(make_bad_data_err(
-                "Unexpected bytes in message length string",
-            ))
+impl<'a> Decoder<'a> {
+    pub fn new(src: &'a [u8]) -> Decoder<'a> {
+        Self::with_options(src, DecodeOptions::default())
+    }
+
+    pub fn with_options(src: &'a [u8], options: DecodeOptions) -> Decoder<'a> {
+        Decoder {
+            src,
+            pos: 0,
+            path: Vec::new(),
+            options,
+            depth: 0,
+            items: 0,
+            warnings: Vec::new(),
+            spans: Spans::default(),
         }
-    };
-    let num = match str::parse::<isize>(&number_str) {
-        Ok(n) => n,
-        Err(_) => {
-            return Err(make_bad_data_err(&format!(
-                "Error parsing isize from str: {}",
-                &number_str
-            )))
+    }
+
+    /// Byte ranges of the values decoded so far. Empty unless `record_spans` is set.
+    pub fn spans(&self) -> &Spans {
+        &self.spans
+    }
+
+    pub fn take_spans(&mut self) -> Spans {
+        core::mem::take(&mut self.spans)
+    }
+
+    /// Spec violations accepted so far in lenient mode. Each one is the error strict
+    /// decoding would have failed with.
+    pub fn warnings(&self) -> &[BencodeError] {
+        &self.warnings
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<BencodeError> {
+        core::mem::take(&mut self.warnings)
+    }
+
+    /// Number of bytes consumed so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Bytes not yet consumed by the decoder
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.src[self.pos..]
+    }
+
+    pub fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    /// Errors with TrailingData if anything is left after the values decoded so far
+    pub fn finish(&self) -> Result<(), BencodeError> {
+        if self.pos < self.src.len() {
+            return Err(self.err_at(BencodeErrorKind::TrailingData, self.pos));
         }
-    };
-    if num.is_negative() {
-        let err_msg = format!("Negative Message Length String: {num}");
-        return Err(make_bad_data_err(&err_msg));
-    }
-    let num = num as usize;
-    // Take {num} chars from iter
-    let s: Vec<u8> = byte_string.take(num).copied().collect();
-    if s.len() != num {
-        let err_msg = format!(
-            "String passed did not have number of bytes specified.
-            \nExpected: {num} Actual: {}\n
-            {:?}",
-            s.len(),
-            s
-        );
-        return Err(make_bad_data_err(&err_msg));
-    }
-    Ok(Bencode::Message(s))
-}
+        Ok(())
+    }
 
-pub fn decode_list(
-    byte_string: &mut Peekable<Iter<'_, u8>>,
-    mut parent: Vec<Bencode>,
-) -> Result<Bencode, std::io::Error> {
-    while let Some(ch) = byte_string.peek() {
-        match ch {
-            b'l' => {
-                byte_string.next();
-                parent.push(decode_list(byte_string, Vec::<Bencode>::new())?)
-            }
-            b'e' => {
-                byte_string.next();
-                return Ok(Bencode::List(parent));
+    pub fn decode_next(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        let start = self.pos;
+        let val = self.decode_value()?;
+        if self.options.record_spans {
+            // A duplicate key (lenient mode) keeps the span of its first occurrence, same as its value
+            self.spans
+                .0
+                .entry(self.key_path())
+                .or_insert(start..self.pos);
+        }
+        Ok(val)
+    }
+
+    /// Decodes the next value like `decode_next`, but hands it to visitor piece by piece
+    /// instead of building it, so memory use does not grow with the size of the value.
+    /// Limits, lenient mode and error paths work as for `decode_next`. Spans are not recorded.
+    pub fn visit_next<V: Visitor<'a>>(&mut self, visitor: &mut V) -> Result<(), BencodeError> {
+        self.count_value()?;
+        match self.peek() {
+            Some(b'i') => match self.decode_int()? {
+                BencodeRef::Int(i) => visitor.on_int(i),
+                BencodeRef::BigInt(s) => visitor.on_big_int(s),
+                _ => unreachable!("decode_int only returns integers"),
+            },
+            Some(b'0'..=b'9') => {
+                let BencodeRef::Message(m) = self.decode_message()? else {
+                    unreachable!("decode_message only returns messages");
+                };
+                visitor.on_bytes(m);
             }
-            _ => parent.push(Bencode::decode_dispatch(byte_string)?),
+            Some(b'l') => self.visit_list(visitor)?,
+            Some(b'd') => self.visit_dict(visitor)?,
+            Some(a) => return Err(self.err(BencodeErrorKind::UnexpectedByte(a))),
+            None => return Err(self.err(BencodeErrorKind::UnexpectedEof)),
+        }
+        Ok(())
+    }
+
+    fn count_value(&mut self) -> Result<(), BencodeError> {
+        if self.src.len() > self.options.max_input_len {
+            return Err(self.err_at(BencodeErrorKind::InputTooLarge(self.src.len()), 0));
+        }
+        self.items += 1;
+        if self.items > self.options.max_items {
+            return Err(self.err(BencodeErrorKind::TooManyItems));
+        }
+        Ok(())
+    }
+
+    fn decode_value(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        self.count_value()?;
+        match self.peek() {
+            Some(b'i') => self.decode_int(),
+            Some(b'0'..=b'9') => self.decode_message(),
+            Some(b'l') => self.decode_list(),
+            Some(b'd') => self.decode_dict(),
+            Some(a) => Err(self.err(BencodeErrorKind::UnexpectedByte(a))),
+            None => Err(self.err(BencodeErrorKind::UnexpectedEof)),
         }
     }
-    Err(make_bad_data_err("No terminal b'e' found in encoded value"))
-}
 
-pub fn decode_dict(
-    byte_string: &mut Peekable<Iter<'_, u8>>,
-    mut parent: BTreeMap<Vec<u8>, Bencode>,
-) -> Result<Bencode, std::io::Error> {
-    let mut key_val_pairs = Vec::<(Vec<u8>, Bencode)>::new();
-    while let Some(ch) = byte_string.peek() {
-        match ch {
-            b'e' => {
-                byte_string.next();
-                if !check_keys_sorted(&key_val_pairs) {
-                    return Err(make_bad_data_err("Keys in dictionary are unsorted"));
-                }
-                for (k, v) in key_val_pairs {
-                    if let std::collections::btree_map::Entry::Vacant(e) = parent.entry(k.clone()) {
-                        e.insert(v);
-                    } else {
-                        let err_msg = format!(
-                            "Duplicate keys in encoded dictionary. Offender: {}",
-                            escape_u8_slice(&k)
-                        );
-                        return Err(make_bad_data_err(&err_msg));
-                    }
-                }
-                return Ok(Bencode::Dict(parent));
+    ///Assumes cursor starts at the 'i'
+    ///Errors
+    ///     1) No terminal 'e' within input
+    ///     2) String can not be parsed as an int (excluding e)
+    ///     3) Leading Zero/es
+    ///     4) Negative Zero
+    fn decode_int(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        let rest = &self.remaining()[1..];
+        let Some(end) = rest.iter().position(|c| *c == b'e') else {
+            return Err(self.err(BencodeErrorKind::UnterminatedInt));
+        };
+        let digits = &rest[..end];
+        let parsed = match validate_and_parse_int(digits) {
+            Err(kind @ BencodeErrorKind::InvalidInt(_))
+                if self.options.lenient && is_padded_int(digits) =>
+            {
+                self.warn(kind, self.pos)?;
+                parse_int(digits)
+            }
+            res => res,
+        };
+        let val = match parsed {
+            Ok(num) => BencodeRef::Int(num),
+            // digits is known to be ascii at this point
+            Err(BencodeErrorKind::IntOverflow(_)) if self.options.big_ints => {
+                BencodeRef::BigInt(core::str::from_utf8(digits).unwrap())
             }
-            c if !c.is_ascii_digit() => {
-                let rest_of_iter: Vec<u8> = byte_string.copied().collect();
-                let err_msg = format!(
-                    "Ill formatted key within dictionary. \nIter Dump: {}",
-                    escape_u8_slice(&rest_of_iter)
-                );
-                return Err(make_bad_data_err(&err_msg));
+            Err(kind) => return Err(self.err(kind)),
+        };
+        self.pos += end + 2;
+        Ok(val)
+    }
+
+    fn decode_message(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        let rest = self.remaining();
+        let Some(colon) = rest.iter().position(|c| !c.is_ascii_digit()) else {
+            return Err(self.err_at(BencodeErrorKind::UnexpectedEof, self.src.len()));
+        };
+        if rest[colon] != b':' {
+            return Err(self.err_at(
+                BencodeErrorKind::UnexpectedByte(rest[colon]),
+                self.pos + colon,
+            ));
+        }
+        let number_bytes = &rest[..colon];
+        if number_bytes.len() > 1 && number_bytes[0] == b'0' {
+            self.warn(BencodeErrorKind::BadLength(number_bytes.to_vec()), self.pos)?;
+        }
+        // Only ascii digits remain so the utf-8 conversion can not fail
+        let number_str = core::str::from_utf8(number_bytes).unwrap();
+        let Ok(num) = str::parse::<usize>(number_str) else {
+            return Err(self.err(BencodeErrorKind::BadLength(number_bytes.to_vec())));
+        };
+        if num > self.options.max_string_len {
+            return Err(self.err(BencodeErrorKind::StringTooLong(num)));
+        }
+        let start = colon + 1;
+        if rest.len() - start < num {
+            return Err(self.err_at(BencodeErrorKind::UnexpectedEof, self.src.len()));
+        }
+        self.pos += start + num;
+        Ok(BencodeRef::Message(&rest[start..start + num]))
+    }
+
+    fn enter_container(&mut self) -> Result<(), BencodeError> {
+        if self.depth >= self.options.max_depth {
+            return Err(self.err(BencodeErrorKind::TooDeep));
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn decode_list(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        self.enter_container()?;
+        let mut list = Vec::new();
+        while let Some(ch) = self.peek() {
+            if ch == b'e' {
+                self.pos += 1;
+                self.depth -= 1;
+                return Ok(BencodeRef::List(list));
             }
-            _ => { /*leave match*/ }
+            self.path.push(PathStep::Index(list.len()));
+            list.push(self.decode_next()?);
+            self.path.pop();
+        }
+        Err(self.err(BencodeErrorKind::UnexpectedEof))
+    }
+
+    fn decode_dict(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        if self.options.preserve_order {
+            return self.decode_ordered_dict();
         }
-        let key;
-        if let Bencode::Message(k) = decode_message(byte_string)? {
-            key = k;
-        } else {
-            return Err(make_bad_data_err("Dictionary key is not a string"));
+        self.enter_container()?;
+        let mut dict = BTreeMap::<&'a [u8], BencodeRef<'a>>::new();
+        let mut last_key: Option<&'a [u8]> = None;
+        while let Some(ch) = self.peek() {
+            if ch == b'e' {
+                self.pos += 1;
+                self.depth -= 1;
+                return Ok(BencodeRef::Dict(dict));
+            }
+            let key = self.decode_key(|k| dict.contains_key(k), last_key)?;
+            self.path.push(PathStep::Key(key));
+            let val = self.get_value(key)?;
+            self.path.pop();
+            // Only reachable for duplicates in lenient mode. The first occurrence is kept.
+            dict.entry(key).or_insert(val);
+            last_key = Some(key);
         }
-        let val = get_value(byte_string, &key)?;
-        key_val_pairs.push((key, val));
+        Err(self.err(BencodeErrorKind::UnexpectedEof))
     }
-    Ok(Bencode::Stop)
-}
 
-fn check_keys_sorted(key_val_pairs: &Vec<(Vec<u8>, Bencode)>) -> bool {
-    if key_val_pairs.len() < 3 {
-        return true;
+    fn decode_ordered_dict(&mut self) -> Result<BencodeRef<'a>, BencodeError> {
+        self.enter_container()?;
+        let mut entries = Vec::<(&'a [u8], BencodeRef<'a>)>::new();
+        let mut keys = BTreeSet::<&'a [u8]>::new();
+        while let Some(ch) = self.peek() {
+            if ch == b'e' {
+                self.pos += 1;
+                self.depth -= 1;
+                return Ok(BencodeRef::OrderedDict(entries));
+            }
+            let last_key = entries.last().map(|(k, _)| *k);
+            let key = self.decode_key(|k| keys.contains(k), last_key)?;
+            self.path.push(PathStep::Key(key));
+            let val = self.get_value(key)?;
+            self.path.pop();
+            keys.insert(key);
+            entries.push((key, val));
+        }
+        Err(self.err(BencodeErrorKind::UnexpectedEof))
     }
-    let key_ordering = key_val_pairs[0].0.cmp(&key_val_pairs[1].0);
-    (0..key_val_pairs.len() - 1).all(|i| check_sorted(key_val_pairs, i, key_ordering))
-}
 
-// Source: https://rust-lang.github.io/rfcs/2351-is-sorted.html
-fn check_sorted(key_val_pairs: &[(Vec<u8>, Bencode)], i: usize, key_ordering: Ordering) -> bool {
-    key_val_pairs[i].0.cmp(&key_val_pairs[i + 1].0) == key_ordering
-}
+    fn visit_list<V: Visitor<'a>>(&mut self, visitor: &mut V) -> Result<(), BencodeError> {
+        self.enter_container()?;
+        visitor.on_list_start();
+        let mut index = 0;
+        while let Some(ch) = self.peek() {
+            if ch == b'e' {
+                self.pos += 1;
+                self.depth -= 1;
+                visitor.on_list_end();
+                return Ok(());
+            }
+            self.path.push(PathStep::Index(index));
+            self.visit_next(visitor)?;
+            self.path.pop();
+            index += 1;
+        }
+        Err(self.err(BencodeErrorKind::UnexpectedEof))
+    }
 
-fn get_value(
-    byte_string: &mut Peekable<Iter<'_, u8>>,
-    key: &[u8],
-) -> Result<Bencode, std::io::Error> {
-    let val = match byte_string.peek() {
-        Some(ch) => match ch {
-            b'd' => {
-                byte_string.next();
-                decode_dict(byte_string, BTreeMap::<Vec<u8>, Bencode>::new())?
+    // Only the keys are kept, to catch duplicates
+    fn visit_dict<V: Visitor<'a>>(&mut self, visitor: &mut V) -> Result<(), BencodeError> {
+        self.enter_container()?;
+        visitor.on_dict_start();
+        let mut keys = BTreeSet::<&'a [u8]>::new();
+        let mut last_key: Option<&'a [u8]> = None;
+        while let Some(ch) = self.peek() {
+            if ch == b'e' {
+                self.pos += 1;
+                self.depth -= 1;
+                visitor.on_dict_end();
+                return Ok(());
+            }
+            let key = self.decode_key(|k| keys.contains(k), last_key)?;
+            visitor.on_key(key);
+            self.path.push(PathStep::Key(key));
+            if self.peek() == Some(b'e') {
+                return Err(self.err(BencodeErrorKind::MissingValue(key.to_vec())));
             }
-            b'e' => {
-                let err_msg = format!(
-                    "{} has no corresponding value. (dictionary ends early)",
-                    escape_u8_slice(key)
-                );
-                return Err(make_bad_data_err(&err_msg));
+            self.visit_next(visitor)?;
+            self.path.pop();
+            keys.insert(key);
+            last_key = Some(key);
+        }
+        Err(self.err(BencodeErrorKind::UnexpectedEof))
+    }
+
+    // Decodes a dictionary key, checking it comes after last_key and is not already in the
+    // dictionary according to seen
+    fn decode_key(
+        &mut self,
+        seen: impl Fn(&[u8]) -> bool,
+        last_key: Option<&[u8]>,
+    ) -> Result<&'a [u8], BencodeError> {
+        match self.peek() {
+            Some(b'i' | b'l' | b'd') => return Err(self.err(BencodeErrorKind::NonStringKey)),
+            Some(c) if !c.is_ascii_digit() => {
+                return Err(self.err(BencodeErrorKind::UnexpectedByte(c)))
             }
-            _ => Bencode::decode_dispatch(byte_string)?,
-        },
-        None => {
-            let err_msg = format!(
-                "Key: {} has no corresponding value (Iterator Empty)",
-                escape_u8_slice(key)
-            );
-            return Err(make_bad_data_err(&err_msg));
+            _ => {}
+        }
+        let key_start = self.pos;
+        let BencodeRef::Message(key) = self.decode_message()? else {
+            unreachable!("decode_message only returns messages");
+        };
+        if seen(key) {
+            self.warn(BencodeErrorKind::DuplicateKey(key.to_vec()), key_start)?;
+        } else if last_key.is_some_and(|prev| prev > key) {
+            self.warn(BencodeErrorKind::UnsortedKeys(key.to_vec()), key_start)?;
+        }
+        Ok(key)
+    }
+
+    fn get_value(&mut self, key: &[u8]) -> Result<BencodeRef<'a>, BencodeError> {
+        match self.peek() {
+            Some(b'e') => Err(self.err(BencodeErrorKind::MissingValue(key.to_vec()))),
+            _ => self.decode_next(),
+        }
+    }
+
+    // Fails with kind in strict mode, records it and carries on in lenient mode
+    fn warn(&mut self, kind: BencodeErrorKind, offset: usize) -> Result<(), BencodeError> {
+        let err = self.err_at(kind, offset);
+        if !self.options.lenient {
+            return Err(err);
+        }
+        self.warnings.push(err);
+        Ok(())
+    }
+
+    fn err(&self, kind: BencodeErrorKind) -> BencodeError {
+        self.err_at(kind, self.pos)
+    }
+
+    fn err_at(&self, kind: BencodeErrorKind, offset: usize) -> BencodeError {
+        BencodeError::new(kind, offset, self.key_path())
+    }
+
+    fn key_path(&self) -> KeyPath {
+        let path = self
+            .path
+            .iter()
+            .map(|step| match step {
+                PathStep::Key(k) => PathSegment::Key(k.to_vec()),
+                PathStep::Index(i) => PathSegment::Index(*i),
+            })
+            .collect();
+        KeyPath(path)
+    }
+}
+
+// Takes the bytes between the 'i' and the 'e' of an integer. The only valid forms are a
+// lone 0 and an optional '-' followed by digits that do not start with 0.
+fn validate_and_parse_int(bytes: &[u8]) -> Result<i64, BencodeErrorKind> {
+    let valid = match bytes {
+        [b'0'] => true,
+        [b'-', b'1'..=b'9', rest @ ..] | [b'1'..=b'9', rest @ ..] => {
+            rest.iter().all(u8::is_ascii_digit)
         }
+        _ => false,
     };
-    Ok(val)
+    if !valid {
+        return Err(BencodeErrorKind::InvalidInt(bytes.to_vec()));
+    }
+    parse_int(bytes)
+}
+
+// Integers lenient mode lets through: leading zeros and negative zero (i03e, i-0e)
+fn is_padded_int(bytes: &[u8]) -> bool {
+    let digits = bytes.strip_prefix(b"-").unwrap_or(bytes);
+    !digits.is_empty() && digits.iter().all(u8::is_ascii_digit)
+}
+
+// Caller makes sure bytes is an optional '-' followed by ascii digits
+fn parse_int(bytes: &[u8]) -> Result<i64, BencodeErrorKind> {
+    let digits = core::str::from_utf8(bytes).unwrap();
+    str::parse::<i64>(digits).map_err(|_| BencodeErrorKind::IntOverflow(bytes.to_vec()))
 }
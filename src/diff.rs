@@ -0,0 +1,137 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::bencode::Bencode;
+use crate::error::{KeyPath, PathSegment};
+use crate::pretty::digest_summary;
+
+/// One place where two documents differ
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference<'a> {
+    pub path: KeyPath,
+    pub change: Change<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<'a> {
+    /// Only present in the new document
+    Added(&'a Bencode),
+    /// Only present in the old document
+    Removed(&'a Bencode),
+    /// Present in both with different values. Containers only show up here when their
+    /// type changed, otherwise the walk goes into them.
+    Changed { old: &'a Bencode, new: &'a Bencode },
+}
+
+impl Difference<'_> {
+    /// Whether the difference is inside the top level `info` dictionary, which means the two
+    /// documents have different info hashes
+    pub fn in_info(&self) -> bool {
+        matches!(self.path.0.first(), Some(PathSegment::Key(k)) if k == b"info")
+    }
+}
+
+/// Walks both documents and lists every path that was added, removed or changed going from
+/// old to new, in key and index order.
+pub fn diff<'a>(old: &'a Bencode, new: &'a Bencode) -> Vec<Difference<'a>> {
+    let mut differences = Vec::new();
+    walk(old, new, KeyPath::root(), &mut differences);
+    differences
+}
+
+fn walk<'a>(old: &'a Bencode, new: &'a Bencode, path: KeyPath, out: &mut Vec<Difference<'a>>) {
+    if let (Some(a), Some(b)) = (entries(old), entries(new)) {
+        let keys: BTreeSet<&Vec<u8>> = a.keys().chain(b.keys()).copied().collect();
+        for k in keys {
+            let path = path.clone().key(k);
+            match (a.get(k), b.get(k)) {
+                (Some(x), Some(y)) => walk(x, y, path, out),
+                (Some(x), None) => out.push(Difference {
+                    path,
+                    change: Change::Removed(x),
+                }),
+                (None, Some(y)) => out.push(Difference {
+                    path,
+                    change: Change::Added(y),
+                }),
+                (None, None) => unreachable!("key comes from one of the dicts"),
+            }
+        }
+        return;
+    }
+    match (old, new) {
+        (Bencode::List(a), Bencode::List(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let path = path.clone().index(i);
+                match (a.get(i), b.get(i)) {
+                    (Some(x), Some(y)) => walk(x, y, path, out),
+                    (Some(x), None) => out.push(Difference {
+                        path,
+                        change: Change::Removed(x),
+                    }),
+                    (None, Some(y)) => out.push(Difference {
+                        path,
+                        change: Change::Added(y),
+                    }),
+                    (None, None) => unreachable!("index is below one of the lengths"),
+                }
+            }
+        }
+        (a, b) if a != b => out.push(Difference {
+            path,
+            change: Change::Changed { old: a, new: b },
+        }),
+        _ => {}
+    }
+}
+
+// Entries of either kind of dictionary by key. Only the first of duplicate keys in an
+// OrderedDict is compared, which is the one a canonical re-encoding keeps.
+fn entries(val: &Bencode) -> Option<BTreeMap<&Vec<u8>, &Bencode>> {
+    match val {
+        Bencode::Dict(d) => Some(d.iter().collect()),
+        Bencode::OrderedDict(d) => {
+            let mut sorted = BTreeMap::new();
+            for (k, v) in d {
+                sorted.entry(k).or_insert(v);
+            }
+            Some(sorted)
+        }
+        _ => None,
+    }
+}
+
+/// Short form of a value for diff output. Text is quoted, binary strings and long text
+/// become their length and SHA-1, containers their size.
+pub fn summarize(val: &Bencode) -> String {
+    const MAX_TEXT: usize = 256;
+    match val {
+        Bencode::Message(m) => match std::str::from_utf8(m) {
+            Ok(s) if m.len() <= MAX_TEXT => format!("{s:?}"),
+            _ => digest_summary(m),
+        },
+        Bencode::Int(i) => i.to_string(),
+        Bencode::BigInt(s) => s.clone(),
+        Bencode::List(l) => format!("<list of {} items>", l.len()),
+        Bencode::Dict(d) => format!("<dictionary of {} entries>", d.len()),
+        Bencode::OrderedDict(d) => format!("<dictionary of {} entries>", d.len()),
+        Bencode::Stop => "<stop>".to_string(),
+    }
+}
+
+// One line per difference, e.g.  ~ info.name: "a" -> "b"
+impl fmt::Display for Difference<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = match self.path.0.is_empty() {
+            true => "<root>".to_string(),
+            false => self.path.to_string(),
+        };
+        match &self.change {
+            Change::Added(v) => write!(f, "+ {path}: {}", summarize(v)),
+            Change::Removed(v) => write!(f, "- {path}: {}", summarize(v)),
+            Change::Changed { old, new } => {
+                write!(f, "~ {path}: {} -> {}", summarize(old), summarize(new))
+            }
+        }
+    }
+}
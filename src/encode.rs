@@ -0,0 +1,237 @@
+use alloc::collections::BTreeMap;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::bencode::Bencode;
+use crate::error::EncodeError;
+
+/// Where an `Encoder` writes its bytes. With the `std` feature this is any `io::Write`,
+/// without it a `Vec<u8>` (or a `&mut` to one). Both fail with `EncodeError`.
+pub trait Sink {
+    type Error;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+
+    /// Error for `Bencode::Stop`, which has no encoding
+    fn stop_error() -> Self::Error;
+
+    /// Error for a `Bencode::BigInt` that is not an integer
+    fn invalid_big_int_error(digits: &str) -> Self::Error;
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> Sink for W {
+    type Error = EncodeError;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.write_all(bytes).map_err(EncodeError::Io)
+    }
+
+    fn stop_error() -> EncodeError {
+        EncodeError::Stop
+    }
+
+    fn invalid_big_int_error(digits: &str) -> EncodeError {
+        EncodeError::InvalidBigInt(digits.to_string())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Sink for Vec<u8> {
+    type Error = EncodeError;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EncodeError> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn stop_error() -> EncodeError {
+        EncodeError::Stop
+    }
+
+    fn invalid_big_int_error(digits: &str) -> EncodeError {
+        EncodeError::InvalidBigInt(digits.to_string())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<S: Sink> Sink for &mut S {
+    type Error = S::Error;
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), S::Error> {
+        (**self).write_bytes(bytes)
+    }
+
+    fn stop_error() -> S::Error {
+        S::stop_error()
+    }
+
+    fn invalid_big_int_error(digits: &str) -> S::Error {
+        S::invalid_big_int_error(digits)
+    }
+}
+
+/// Writes bencoded values straight into `W` (a file, socket buffer, `Vec<u8>`, ...) without
+/// building intermediate byte vectors.
+pub struct Encoder<W: Sink> {
+    writer: W,
+    preserve_order: bool,
+}
+
+impl<W: Sink> Encoder<W> {
+    pub fn new(writer: W) -> Encoder<W> {
+        Encoder {
+            writer,
+            preserve_order: false,
+        }
+    }
+
+    /// Write `OrderedDict`s exactly as they are, unsorted and with duplicate keys, instead of
+    /// in canonical form. Together with `DecodeOptions::preserve_order` this reproduces the
+    /// dictionaries of a non-canonical document byte for byte.
+    pub fn preserve_order(mut self, preserve: bool) -> Encoder<W> {
+        self.preserve_order = preserve;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn encode(&mut self, val: &Bencode) -> Result<(), W::Error> {
+        match val {
+            Bencode::Int(i) => self.write_int(*i),
+            Bencode::BigInt(s) if !is_integer(s) => Err(W::invalid_big_int_error(s)),
+            Bencode::BigInt(s) => {
+                self.writer.write_bytes(b"i")?;
+                self.writer.write_bytes(s.as_bytes())?;
+                self.writer.write_bytes(b"e")
+            }
+            Bencode::Message(s) => self.write_message(s),
+            Bencode::List(l) => self.write_list(l),
+            Bencode::Dict(d) => self.write_entries(d.iter()),
+            Bencode::OrderedDict(d) if self.preserve_order => {
+                self.write_entries(d.iter().map(|(k, v)| (k, v)))
+            }
+            Bencode::OrderedDict(d) => self.write_entries(canonical_entries(d).into_iter()),
+            Bencode::Stop => Err(W::stop_error()),
+        }
+    }
+
+    pub fn write_int(&mut self, i: i64) -> Result<(), W::Error> {
+        let mut buf = [0; 20];
+        self.writer.write_bytes(b"i")?;
+        if i < 0 {
+            self.writer.write_bytes(b"-")?;
+        }
+        self.writer
+            .write_bytes(decimal(i.unsigned_abs(), &mut buf))?;
+        self.writer.write_bytes(b"e")
+    }
+
+    pub fn write_message(&mut self, s: &[u8]) -> Result<(), W::Error> {
+        let mut buf = [0; 20];
+        self.writer.write_bytes(decimal(s.len() as u64, &mut buf))?;
+        self.writer.write_bytes(b":")?;
+        self.writer.write_bytes(s)
+    }
+
+    fn write_list(&mut self, l: &[Bencode]) -> Result<(), W::Error> {
+        self.writer.write_bytes(b"l")?;
+        for val in l {
+            self.encode(val)?;
+        }
+        self.writer.write_bytes(b"e")
+    }
+
+    fn write_entries<'a>(
+        &mut self,
+        entries: impl Iterator<Item = (&'a Vec<u8>, &'a Bencode)>,
+    ) -> Result<(), W::Error> {
+        self.writer.write_bytes(b"d")?;
+        for (k, v) in entries {
+            self.write_message(k)?;
+            self.encode(v)?;
+        }
+        self.writer.write_bytes(b"e")
+    }
+}
+
+// Writes the decimal digits of n to the end of buf and returns them. 20 digits fit any u64.
+fn decimal(mut n: u64, buf: &mut [u8; 20]) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (n % 10) as u8;
+        n /= 10;
+        if n == 0 {
+            return &buf[start..];
+        }
+    }
+}
+
+// Whether s is an integer in canonical form: digits without leading zeros, optionally led by
+// '-', and no negative zero
+pub(crate) fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty()
+        && digits.bytes().all(|c| c.is_ascii_digit())
+        && (digits == "0" || !digits.starts_with('0'))
+        && s != "-0"
+}
+
+// Sorted entries of an ordered dict, keeping the first of any duplicate keys like the decoder
+fn canonical_entries(d: &[(Vec<u8>, Bencode)]) -> BTreeMap<&Vec<u8>, &Bencode> {
+    let mut sorted = BTreeMap::new();
+    for (k, v) in d {
+        sorted.entry(k).or_insert(v);
+    }
+    sorted
+}
+
+// Number of characters in the decimal representation of n
+fn decimal_len<T: Into<u64>>(n: T) -> usize {
+    let mut n = n.into();
+    let mut len = 1;
+    while n >= 10 {
+        n /= 10;
+        len += 1;
+    }
+    len
+}
+
+/// Number of bytes `val` takes up once encoded. Stop has no encoding and counts as 0.
+pub fn encoded_len(val: &Bencode) -> usize {
+    len_of(val, false)
+}
+
+/// Like `encoded_len`, for an `Encoder` that preserves order
+pub fn encoded_len_preserving_order(val: &Bencode) -> usize {
+    len_of(val, true)
+}
+
+fn len_of(val: &Bencode, preserve_order: bool) -> usize {
+    let entries_len = |(k, v): (&Vec<u8>, &Bencode)| message_len(k) + len_of(v, preserve_order);
+    match val {
+        Bencode::Int(i) => 2 + decimal_len(i.unsigned_abs()) + usize::from(*i < 0),
+        Bencode::BigInt(s) => 2 + s.len(),
+        Bencode::Message(s) => message_len(s),
+        Bencode::List(l) => 2 + l.iter().map(|v| len_of(v, preserve_order)).sum::<usize>(),
+        Bencode::Dict(d) => 2 + d.iter().map(entries_len).sum::<usize>(),
+        Bencode::OrderedDict(d) if preserve_order => {
+            2 + d.iter().map(|(k, v)| entries_len((k, v))).sum::<usize>()
+        }
+        // Counts the entries the Encoder writes, the first of each key
+        Bencode::OrderedDict(d) => {
+            2 + canonical_entries(d)
+                .into_iter()
+                .map(entries_len)
+                .sum::<usize>()
+        }
+        Bencode::Stop => 0,
+    }
+}
+
+fn message_len(s: &[u8]) -> usize {
+    decimal_len(s.len() as u64) + 1 + s.len()
+}
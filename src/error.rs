@@ -0,0 +1,269 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self};
+
+use crate::escape_u8_slice;
+
+/// Error produced while decoding bencode. `offset` is the index of the offending byte in the
+/// decoded input and `path` is where in the document that byte sits, e.g. `info.files[3].path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BencodeError {
+    pub kind: BencodeErrorKind,
+    pub offset: usize,
+    pub path: KeyPath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BencodeErrorKind {
+    /// A byte that can not start (or continue) a value at this position
+    UnexpectedByte(u8),
+    /// Input ended in the middle of a value
+    UnexpectedEof,
+    /// An integer with no terminal 'e'
+    UnterminatedInt,
+    /// Integer digits that are not valid bencode (leading zeros, negative zero, stray bytes)
+    InvalidInt(Vec<u8>),
+    /// Integer that does not fit in the integer type
+    IntOverflow(Vec<u8>),
+    /// Message length prefix that is not a plain decimal number
+    BadLength(Vec<u8>),
+    /// Dictionary key that is not a message
+    NonStringKey,
+    /// Dictionary key that sorts before the key preceding it
+    UnsortedKeys(Vec<u8>),
+    /// Dictionary key that is equal to the key preceding it
+    DuplicateKey(Vec<u8>),
+    /// Dictionary that ends right after a key
+    MissingValue(Vec<u8>),
+    /// Bytes left over after a complete value
+    TrailingData,
+    /// Lists and dictionaries nested deeper than `DecodeOptions::max_depth`
+    TooDeep,
+    /// Message length prefix larger than `DecodeOptions::max_string_len`
+    StringTooLong(usize),
+    /// More values than `DecodeOptions::max_items`
+    TooManyItems,
+    /// Input longer than `DecodeOptions::max_input_len`
+    InputTooLarge(usize),
+}
+
+/// A single step into a bencoded document
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PathSegment {
+    Key(Vec<u8>),
+    Index(usize),
+}
+
+/// Location of a value within a bencoded document, as a list of dictionary keys and list indexes
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct KeyPath(pub Vec<PathSegment>);
+
+impl KeyPath {
+    /// Path of the top level value
+    pub fn root() -> KeyPath {
+        KeyPath::default()
+    }
+
+    /// Extends the path into the dictionary entry under key
+    pub fn key(mut self, key: &[u8]) -> KeyPath {
+        self.0.push(PathSegment::Key(key.to_vec()));
+        self
+    }
+
+    /// Extends the path into the list element at index
+    pub fn index(mut self, index: usize) -> KeyPath {
+        self.0.push(PathSegment::Index(index));
+        self
+    }
+}
+
+impl BencodeError {
+    pub fn new(kind: BencodeErrorKind, offset: usize, path: KeyPath) -> BencodeError {
+        BencodeError { kind, offset, path }
+    }
+}
+
+impl fmt::Display for BencodeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BencodeErrorKind::UnexpectedByte(b) => {
+                write!(f, "unexpected byte '{}'", escape_u8_slice(&[*b]))
+            }
+            BencodeErrorKind::UnexpectedEof => write!(f, "unexpected end of input"),
+            BencodeErrorKind::UnterminatedInt => write!(f, "integer has no terminal 'e'"),
+            BencodeErrorKind::InvalidInt(i) => {
+                write!(f, "malformed integer '{}'", escape_u8_slice(i))
+            }
+            BencodeErrorKind::IntOverflow(i) => {
+                write!(f, "integer '{}' is out of range", escape_u8_slice(i))
+            }
+            BencodeErrorKind::BadLength(l) => {
+                write!(f, "bad message length '{}'", escape_u8_slice(l))
+            }
+            BencodeErrorKind::NonStringKey => write!(f, "dictionary key is not a string"),
+            BencodeErrorKind::UnsortedKeys(k) => {
+                write!(f, "dictionary key '{}' is out of order", escape_u8_slice(k))
+            }
+            BencodeErrorKind::DuplicateKey(k) => {
+                write!(f, "duplicate dictionary key '{}'", escape_u8_slice(k))
+            }
+            BencodeErrorKind::MissingValue(k) => {
+                write!(f, "dictionary key '{}' has no value", escape_u8_slice(k))
+            }
+            BencodeErrorKind::TrailingData => write!(f, "trailing data after value"),
+            BencodeErrorKind::TooDeep => write!(f, "nesting depth limit exceeded"),
+            BencodeErrorKind::StringTooLong(n) => {
+                write!(f, "message of {n} bytes exceeds the length limit")
+            }
+            BencodeErrorKind::TooManyItems => write!(f, "item count limit exceeded"),
+            BencodeErrorKind::InputTooLarge(n) => {
+                write!(f, "input of {n} bytes exceeds the size limit")
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(k) if i == 0 => write!(f, "{}", escape_u8_slice(k))?,
+                PathSegment::Key(k) => write!(f, ".{}", escape_u8_slice(k))?,
+                PathSegment::Index(n) => write!(f, "[{n}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BencodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)?;
+        if !self.path.0.is_empty() {
+            write!(f, " (in {})", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for BencodeError {}
+
+#[cfg(feature = "std")]
+impl From<BencodeError> for std::io::Error {
+    fn from(err: BencodeError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// A `Bencode` value converted into a type it does not hold, e.g. a list read as an integer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub expected: &'static str,
+    pub found: String,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl core::error::Error for TypeError {}
+
+#[cfg(feature = "std")]
+impl From<TypeError> for std::io::Error {
+    fn from(err: TypeError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Error from an `Encoder` writing into a `Vec<u8>` or, with the `std` feature, any
+/// `io::Write`. The same type in both builds, so code matching on `Stop` keeps compiling when
+/// another crate turns `std` on.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// `Bencode::Stop`, which has no encoding
+    Stop,
+    /// `Bencode::BigInt` holding something other than the digits of an integer, which would
+    /// otherwise be written out as it is
+    InvalidBigInt(String),
+    /// The writer failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Stop => write!(f, "Stop val passed to encode."),
+            EncodeError::InvalidBigInt(s) => write!(f, "Invalid big integer {s:?}."),
+            #[cfg(feature = "std")]
+            EncodeError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            EncodeError::Stop | EncodeError::InvalidBigInt(_) => None,
+            #[cfg(feature = "std")]
+            EncodeError::Io(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<EncodeError> for std::io::Error {
+    fn from(err: EncodeError) -> Self {
+        match err {
+            EncodeError::Io(err) => err,
+            err => std::io::Error::new(std::io::ErrorKind::InvalidInput, err),
+        }
+    }
+}
+
+/// Error produced by the serde `to_bytes`/`from_bytes` bridge
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerdeError {
+    /// The input was not valid bencode
+    Decode(BencodeError),
+    /// The value could not be mapped to or from bencode
+    Message(String),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for SerdeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerdeError::Decode(e) => write!(f, "{e}"),
+            SerdeError::Message(m) => write!(f, "{m}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for SerdeError {}
+
+#[cfg(feature = "std")]
+impl From<BencodeError> for SerdeError {
+    fn from(err: BencodeError) -> Self {
+        SerdeError::Decode(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl serde::ser::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
+
+#[cfg(feature = "std")]
+impl serde::de::Error for SerdeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeError::Message(msg.to_string())
+    }
+}
@@ -1,39 +1,66 @@
 use std::collections::BTreeMap;
 
 use crate::bencode::Bencode;
+use crate::{make_bad_data_err, message_list, optional, required};
 type ByteString = Vec<u8>;
 
 pub struct FileDict {
-    pub piece_length: isize,
-    pub pieces: Vec<ByteString>,
+    pub piece_length: i64,
+    /// v1 SHA-1 piece hashes. None for a v2 only torrent.
+    pub pieces: Option<Vec<ByteString>>,
     pub single_file: bool,
     pub files: Option<Vec<FileInfo>>,
     pub name: ByteString,
-    pub file_length: Option<isize>,
+    pub file_length: Option<i64>,
+    /// 1 for v1 torrents, 2 for v2 (BEP 52) and hybrid torrents
+    pub meta_version: i64,
+    /// Every file in the v2 `file tree`, in tree order. None for a v1 torrent.
+    pub file_tree: Option<Vec<TreeFile>>,
+}
+
+/// A file in a v2 file tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeFile {
+    pub path: Vec<ByteString>,
+    pub length: i64,
+    /// Root of the file's SHA-256 merkle tree. None for an empty file.
+    pub pieces_root: Option<[u8; 32]>,
 }
 
 impl FileDict {
-    pub fn construct_from_info(bencode_dict: &Bencode) -> FileDict {
-        let info_dict = bencode_dict.unwrap_dict();
-        let piece_len = info_dict
-            .get("piece length".as_bytes())
-            .expect("No length value in info dict")
-            .unwrap_int();
-        let pieces = info_dict
-            .get("pieces".as_bytes())
-            .expect("No pieces value in info dict")
-            .unwrap_message();
-        if pieces.len() % 20 != 0 {
-            panic!("pieces is not a multiple of 20");
+    // v1 keys are read when `pieces` is there, the `file tree` when `meta version` is 2, and
+    // a hybrid torrent has both. For a v2 only torrent the v1 style fields (single_file,
+    // file_length, files) are filled in from the file tree.
+    pub fn construct_from_info(bencode_dict: &Bencode) -> Result<FileDict, std::io::Error> {
+        let Some(info_dict) = bencode_dict.as_dict() else {
+            return Err(make_bad_data_err("info is not a dictionary"));
+        };
+        let piece_len = required(info_dict, "piece length")?;
+        if piece_len <= 0 {
+            return Err(make_bad_data_err("piece length is not positive"));
         }
-        let piece_hashes: Vec<ByteString> = pieces
-            .chunks_exact(20)
-            .map(|chunk| chunk.to_vec())
-            .collect();
-        let mut file_length: Option<isize> = None;
+        let meta_version = optional(info_dict, "meta version")?.unwrap_or(1);
+        let file_tree = match meta_version {
+            1 => None,
+            2 => Some(Self::v2_files(info_dict, piece_len)?),
+            v => return Err(make_bad_data_err(&format!("Unsupported meta version {v}"))),
+        };
+        let pieces = match (optional::<&[u8]>(info_dict, "pieces")?, &file_tree) {
+            (Some(pieces), _) => Some(Self::piece_hashes(pieces)?),
+            (None, Some(_)) => None,
+            (None, None) => return Err(make_bad_data_err("No 'pieces' key")),
+        };
+        let mut file_length: Option<i64> = None;
         let file_name: ByteString;
         let mut file_list: Option<Vec<FileInfo>> = None;
-        let single_file = match FileOrDir::from_dict(info_dict) {
+        let layout = match (&pieces, &file_tree) {
+            (None, Some(tree)) => FileOrDir::from_tree(info_dict, tree)?,
+            _ => FileOrDir::from_dict(info_dict)?,
+        };
+        if let (Some(pieces), Some(tree)) = (&pieces, &file_tree) {
+            check_hybrid(&layout, tree, piece_len, pieces.len())?;
+        }
+        let single_file = match layout {
             FileOrDir::Single(SingleFileInfo { name, length }) => {
                 file_length = Some(length);
                 file_name = name;
@@ -45,15 +72,148 @@ impl FileDict {
                 false
             }
         };
-        FileDict {
+        Ok(FileDict {
             piece_length: piece_len,
-            pieces: piece_hashes,
+            pieces,
             single_file,
             file_length,
             name: file_name,
             files: file_list,
+            meta_version,
+            file_tree,
+        })
+    }
+
+    fn piece_hashes(pieces: &[u8]) -> Result<Vec<ByteString>, std::io::Error> {
+        if !pieces.len().is_multiple_of(20) {
+            return Err(make_bad_data_err("pieces is not a multiple of 20"));
+        }
+        Ok(pieces
+            .chunks_exact(20)
+            .map(|chunk| chunk.to_vec())
+            .collect())
+    }
+
+    // BEP 52 wants a power of two of at least 16 KiB, so pieces line up with merkle tree leaves
+    fn v2_files(
+        info_dict: &BTreeMap<ByteString, Bencode>,
+        piece_len: i64,
+    ) -> Result<Vec<TreeFile>, std::io::Error> {
+        if piece_len < 16384 || piece_len.count_ones() != 1 {
+            return Err(make_bad_data_err(
+                "v2 piece length is not a power of two of at least 16 KiB",
+            ));
+        }
+        let tree: &BTreeMap<ByteString, Bencode> = required(info_dict, "file tree")?;
+        let mut files = Vec::new();
+        walk_file_tree(tree, &mut Vec::new(), &mut files)?;
+        if files.is_empty() {
+            return Err(make_bad_data_err("file tree is empty"));
+        }
+        Ok(files)
+    }
+}
+
+// The v1 and v2 halves of a hybrid torrent have to describe the same files in the same
+// order. On the v1 side every file starts on a piece boundary, with padding files making up
+// the gaps, so v1 pieces and v2 pieces line up.
+fn check_hybrid(
+    v1: &FileOrDir,
+    tree: &[TreeFile],
+    piece_len: i64,
+    piece_count: usize,
+) -> Result<(), std::io::Error> {
+    let v1_files: Vec<(&[ByteString], i64)> = match v1 {
+        FileOrDir::Single(file) => vec![(std::slice::from_ref(&file.name), file.length)],
+        FileOrDir::Multi(dir) => dir
+            .files
+            .iter()
+            .filter(|file| !file.is_padding())
+            .map(|file| (file.path.as_slice(), file.length))
+            .collect(),
+    };
+    let v2_files = tree.iter().map(|file| (file.path.as_slice(), file.length));
+    if !v1_files.iter().copied().eq(v2_files) {
+        return Err(make_bad_data_err(
+            "v1 file list and v2 file tree describe different files",
+        ));
+    }
+    let total_len = match v1 {
+        FileOrDir::Single(file) => file.length,
+        FileOrDir::Multi(dir) => {
+            let mut offset: i64 = 0;
+            for file in &dir.files {
+                if !file.is_padding() && file.length > 0 && offset % piece_len != 0 {
+                    return Err(make_bad_data_err(
+                        "v1 file does not start on a piece boundary",
+                    ));
+                }
+                // Lengths come from the torrent, so their sum can't be trusted to fit
+                offset = match offset.checked_add(file.length) {
+                    Some(offset) if file.length >= 0 => offset,
+                    _ => return Err(make_bad_data_err("v1 file lengths are out of range")),
+                };
+            }
+            offset
         }
+    };
+    if (total_len as u64).div_ceil(piece_len as u64) != piece_count as u64 {
+        return Err(make_bad_data_err("v1 pieces do not cover the files"));
     }
+    Ok(())
+}
+
+// A directory maps names to subdirectories, a file is a dictionary with a single "" key
+// holding its length and pieces root
+fn walk_file_tree(
+    dir: &BTreeMap<ByteString, Bencode>,
+    path: &mut Vec<ByteString>,
+    out: &mut Vec<TreeFile>,
+) -> Result<(), std::io::Error> {
+    for (name, node) in dir {
+        if name.is_empty() {
+            return Err(make_bad_data_err("Empty name in file tree"));
+        }
+        let Some(node) = node.as_dict() else {
+            return Err(make_bad_data_err("Entry in file tree is not a dictionary"));
+        };
+        path.push(name.clone());
+        match node.get("".as_bytes()) {
+            Some(file) => out.push(tree_file(file, path)?),
+            None => walk_file_tree(node, path, out)?,
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+fn tree_file(file: &Bencode, path: &[ByteString]) -> Result<TreeFile, std::io::Error> {
+    let Some(d) = file.as_dict() else {
+        return Err(make_bad_data_err("File in file tree is not a dictionary"));
+    };
+    let length = file_length(d)?;
+    let pieces_root = match optional::<&[u8]>(d, "pieces root")? {
+        Some(root) => Some(
+            <[u8; 32]>::try_from(root)
+                .map_err(|_| make_bad_data_err("'pieces root' is not 32 bytes"))?,
+        ),
+        None if length > 0 => return Err(make_bad_data_err("No 'pieces root' key")),
+        None => None,
+    };
+    Ok(TreeFile {
+        path: path.to_vec(),
+        length,
+        pieces_root,
+    })
+}
+
+// The "length" of a file, which can be 0 but not negative
+fn file_length(d: &BTreeMap<ByteString, Bencode>) -> Result<i64, std::io::Error> {
+    let length = required(d, "length")?;
+    if length < 0 {
+        return Err(make_bad_data_err("File length is negative"));
+    }
+    Ok(length)
 }
 
 enum FileOrDir {
@@ -62,59 +222,79 @@ enum FileOrDir {
 }
 
 impl FileOrDir {
-    fn extract_file_info(file_info_bencoded: &Vec<Bencode>) -> Vec<FileInfo> {
+    fn extract_file_info(file_info_bencoded: &[Bencode]) -> Result<Vec<FileInfo>, std::io::Error> {
         let mut file_info_extracted: Vec<FileInfo> = Vec::with_capacity(file_info_bencoded.len());
         for ben_val in file_info_bencoded {
-            let d = ben_val.unwrap_dict();
+            let Some(d) = ben_val.as_dict() else {
+                return Err(make_bad_data_err("Entry in files is not a dictionary"));
+            };
+            let path = d
+                .get("path".as_bytes())
+                .ok_or_else(|| make_bad_data_err("No 'path' key in files"))?;
             file_info_extracted.push(FileInfo {
-                length: d
-                    .get("length".as_bytes())
-                    .expect("Bad length in files")
-                    .unwrap_int(),
-                path: d
-                    .get("path".as_bytes())
-                    .expect("Bad path in files")
-                    .unwrap_list()
+                length: file_length(d)?,
+                path: message_list(path, "path")?,
+                attr: optional::<&[u8]>(d, "attr")?
+                    .map(<[u8]>::to_vec)
+                    .unwrap_or_default(),
+            })
+        }
+        Ok(file_info_extracted)
+    }
+    // Single file when the tree holds one file named after the torrent, as BEP 52 lays it out
+    fn from_tree(
+        dict: &BTreeMap<ByteString, Bencode>,
+        tree: &[TreeFile],
+    ) -> Result<FileOrDir, std::io::Error> {
+        let name = required::<&[u8]>(dict, "name")?.to_vec();
+        match tree {
+            [file] if file.path == [name.clone()] => Ok(FileOrDir::Single(SingleFileInfo {
+                name,
+                length: file.length,
+            })),
+            _ => Ok(FileOrDir::Multi(MultiFileInfo {
+                dir_name: name,
+                files: tree
                     .iter()
-                    .map(|v| v.unwrap_message())
+                    .map(|file| FileInfo {
+                        length: file.length,
+                        path: file.path.clone(),
+                        attr: Vec::new(),
+                    })
                     .collect(),
-            })
+            })),
         }
-        file_info_extracted
     }
-    pub fn from_dict(dict: &BTreeMap<ByteString, Bencode>) -> FileOrDir {
-        if dict.contains_key("files".as_bytes()) {
-            let file_info_bencoded: Vec<Bencode> = match dict.get("files".as_bytes()).unwrap() {
-                Bencode::List(l) => l.to_vec(),
-                _ => panic!("files maps to a non bencoded list"),
-            };
-            let file_info_list = Self::extract_file_info(&file_info_bencoded);
-            return FileOrDir::Multi(MultiFileInfo {
-                dir_name: dict
-                    .get("name".as_bytes())
-                    .expect("No name key")
-                    .unwrap_message(),
-                files: file_info_list,
-            });
-        } else {
-            let s_file_info = SingleFileInfo {
-                name: dict
-                    .get("name".as_bytes())
-                    .expect("No name key")
-                    .unwrap_message(),
-                length: dict
-                    .get("length".as_bytes())
-                    .expect("No length key")
-                    .unwrap_int(),
-            };
-            FileOrDir::Single(s_file_info)
+
+    pub fn from_dict(dict: &BTreeMap<ByteString, Bencode>) -> Result<FileOrDir, std::io::Error> {
+        let name = required::<&[u8]>(dict, "name")?.to_vec();
+        match optional::<&[Bencode]>(dict, "files")? {
+            Some(files) => Ok(FileOrDir::Multi(MultiFileInfo {
+                dir_name: name,
+                files: Self::extract_file_info(files)?,
+            })),
+            None => Ok(FileOrDir::Single(SingleFileInfo {
+                name,
+                length: file_length(dict)?,
+            })),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileInfo {
-    length: isize,
-    path: Vec<ByteString>,
+    pub length: i64,
+    pub path: Vec<ByteString>,
+    /// BEP 47 attribute flags, empty when the key is missing
+    pub attr: ByteString,
+}
+
+impl FileInfo {
+    /// Padding files ('p' attribute) are zeros inserted to align the next file to a piece
+    /// boundary. They are in the v1 file list only and never written to disk.
+    pub fn is_padding(&self) -> bool {
+        self.attr.contains(&b'p')
+    }
 }
 
 pub struct MultiFileInfo {
@@ -124,5 +304,5 @@ pub struct MultiFileInfo {
 
 pub struct SingleFileInfo {
     name: ByteString,
-    length: isize,
+    length: i64,
 }
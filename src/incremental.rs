@@ -0,0 +1,295 @@
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::bencode::Bencode;
+use crate::decode::{DecodeOptions, Decoder};
+use crate::error::{BencodeError, BencodeErrorKind, KeyPath, PathSegment};
+
+/// Result of feeding bytes to an `IncrementalDecoder`
+#[derive(Debug, PartialEq)]
+pub enum Progress {
+    /// The bytes seen so far are a valid prefix of a value, but the value is not finished
+    NeedMore,
+    /// A whole value was decoded. Bytes after it stay buffered for the next value.
+    Complete(Bencode),
+}
+
+/// Push based decoder for input that arrives in pieces (sockets, tracker responses,
+/// ut_metadata pieces). Lists and dictionaries that are already open are kept on a stack
+/// between calls, so only a string or integer cut off mid-way is looked at again.
+/// The decoder should be discarded once it has returned an error.
+#[derive(Default)]
+pub struct IncrementalDecoder {
+    buf: Vec<u8>,
+    // Bytes dropped from the front of buf, used to report offsets into the whole stream
+    base: usize,
+    stack: Vec<Frame>,
+    options: DecodeOptions,
+    // Values started so far, checked against options.max_items
+    items: usize,
+    // Spec violations let through in lenient mode
+    warnings: Vec<BencodeError>,
+}
+
+enum Frame {
+    List(Vec<Bencode>),
+    Dict {
+        entries: DictEntries,
+        key: Option<Vec<u8>>,
+    },
+}
+
+// Entries of an open dictionary, kept in input order when options.preserve_order is set
+enum DictEntries {
+    Sorted {
+        dict: BTreeMap<Vec<u8>, Bencode>,
+        // Key inserted last, which is not the largest one once keys are out of order
+        last_key: Option<Vec<u8>>,
+    },
+    Ordered {
+        entries: Vec<(Vec<u8>, Bencode)>,
+        keys: BTreeSet<Vec<u8>>,
+    },
+}
+
+impl DictEntries {
+    fn contains(&self, k: &[u8]) -> bool {
+        match self {
+            DictEntries::Sorted { dict, .. } => dict.contains_key(k),
+            DictEntries::Ordered { keys, .. } => keys.contains(k),
+        }
+    }
+
+    // Key new keys are checked against for order, the one just before them like Decoder
+    fn last_key(&self) -> Option<&[u8]> {
+        match self {
+            DictEntries::Sorted { last_key, .. } => last_key.as_deref(),
+            DictEntries::Ordered { entries, .. } => entries.last().map(|(k, _)| k.as_slice()),
+        }
+    }
+
+    fn insert(&mut self, k: Vec<u8>, val: Bencode) {
+        match self {
+            // Only a duplicate in lenient mode can be occupied. The first occurrence is kept.
+            DictEntries::Sorted { dict, last_key } => {
+                *last_key = Some(k.clone());
+                dict.entry(k).or_insert(val);
+            }
+            DictEntries::Ordered { entries, keys } => {
+                keys.insert(k.clone());
+                entries.push((k, val));
+            }
+        }
+    }
+
+    fn into_bencode(self) -> Bencode {
+        match self {
+            DictEntries::Sorted { dict, .. } => Bencode::Dict(dict),
+            DictEntries::Ordered { entries, .. } => Bencode::OrderedDict(entries),
+        }
+    }
+}
+
+impl IncrementalDecoder {
+    pub fn new() -> IncrementalDecoder {
+        IncrementalDecoder::default()
+    }
+
+    /// The limits apply to the whole stream: `max_input_len` caps the total bytes fed and
+    /// `max_items` the values decoded across every `Complete`.
+    pub fn with_options(options: DecodeOptions) -> IncrementalDecoder {
+        IncrementalDecoder {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Spec violations accepted so far in lenient mode, with offsets into the whole stream
+    pub fn warnings(&self) -> &[BencodeError] {
+        &self.warnings
+    }
+
+    /// Bytes received but not yet part of a decoded value
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Appends chunk to the input and decodes as far as possible. Call with an empty chunk
+    /// to decode a value that is already sitting in the buffer after a `Complete`.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Progress, BencodeError> {
+        let total = self.base + self.buf.len() + chunk.len();
+        if total > self.options.max_input_len {
+            return Err(self.err(BencodeErrorKind::InputTooLarge(total), self.buf.len()));
+        }
+        self.buf.extend_from_slice(chunk);
+        let mut pos = 0;
+        let res = self.parse(&mut pos);
+        self.buf.drain(..pos);
+        self.base += pos;
+        res
+    }
+
+    fn parse(&mut self, pos: &mut usize) -> Result<Progress, BencodeError> {
+        while let Some(&ch) = self.buf.get(*pos) {
+            let awaiting_key = matches!(self.stack.last(), Some(Frame::Dict { key: None, .. }));
+            let (val, len) = match ch {
+                b'e' => {
+                    let Some(frame) = self.stack.pop() else {
+                        return Err(self.err(BencodeErrorKind::UnexpectedByte(ch), *pos));
+                    };
+                    match frame {
+                        Frame::List(l) => (Bencode::List(l), 1),
+                        Frame::Dict { entries, key: None } => (entries.into_bencode(), 1),
+                        Frame::Dict { key: Some(k), .. } => {
+                            return Err(self.err(BencodeErrorKind::MissingValue(k), *pos))
+                        }
+                    }
+                }
+                b'i' | b'l' | b'd' if awaiting_key => {
+                    return Err(self.err(BencodeErrorKind::NonStringKey, *pos))
+                }
+                b'l' => {
+                    self.enter_container(*pos)?;
+                    *pos += 1;
+                    self.stack.push(Frame::List(Vec::new()));
+                    continue;
+                }
+                b'd' => {
+                    self.enter_container(*pos)?;
+                    *pos += 1;
+                    let entries = match self.options.preserve_order {
+                        true => DictEntries::Ordered {
+                            entries: Vec::new(),
+                            keys: BTreeSet::new(),
+                        },
+                        false => DictEntries::Sorted {
+                            dict: BTreeMap::new(),
+                            last_key: None,
+                        },
+                    };
+                    self.stack.push(Frame::Dict { entries, key: None });
+                    continue;
+                }
+                _ => {
+                    let options = DecodeOptions {
+                        max_items: usize::MAX,
+                        ..self.options
+                    };
+                    let mut decoder = Decoder::with_options(&self.buf[*pos..], options);
+                    match decoder.decode_next() {
+                        Ok(val) => {
+                            let len = decoder.position();
+                            let val = val.into_owned();
+                            for w in decoder.take_warnings() {
+                                let warning = self.err(w.kind, *pos + w.offset);
+                                self.warnings.push(warning);
+                            }
+                            if !awaiting_key {
+                                self.count_item(*pos)?;
+                            }
+                            (val, len)
+                        }
+                        Err(e) if is_incomplete(&e, &self.buf[*pos..]) => {
+                            return Ok(Progress::NeedMore)
+                        }
+                        Err(e) => return Err(self.err(e.kind, *pos + e.offset)),
+                    }
+                }
+            };
+            let offset = *pos;
+            *pos += len;
+            if let Some(done) = self.attach(val, offset)? {
+                return Ok(Progress::Complete(done));
+            }
+        }
+        Ok(Progress::NeedMore)
+    }
+
+    fn count_item(&mut self, pos: usize) -> Result<(), BencodeError> {
+        self.items += 1;
+        if self.items > self.options.max_items {
+            return Err(self.err(BencodeErrorKind::TooManyItems, pos));
+        }
+        Ok(())
+    }
+
+    fn enter_container(&mut self, pos: usize) -> Result<(), BencodeError> {
+        if self.stack.len() >= self.options.max_depth {
+            return Err(self.err(BencodeErrorKind::TooDeep, pos));
+        }
+        self.count_item(pos)
+    }
+
+    // Adds a finished value to the innermost open container. Returns the value back if
+    // nothing is open, meaning it is a complete top level value.
+    fn attach(&mut self, val: Bencode, offset: usize) -> Result<Option<Bencode>, BencodeError> {
+        let violation = match (self.stack.last(), &val) {
+            (Some(Frame::Dict { key: None, .. }), Bencode::Message(k)) => self.check_key(k),
+            (Some(Frame::Dict { key: None, .. }), _) => {
+                return Err(self.err(BencodeErrorKind::NonStringKey, offset))
+            }
+            _ => None,
+        };
+        if let Some(kind) = violation {
+            self.warn(kind, offset)?;
+        }
+        match self.stack.last_mut() {
+            None => return Ok(Some(val)),
+            Some(Frame::List(l)) => l.push(val),
+            Some(Frame::Dict { entries, key }) => match (key.take(), val) {
+                (Some(k), val) => entries.insert(k, val),
+                (None, Bencode::Message(k)) => *key = Some(k),
+                (None, _) => unreachable!("non message keys are rejected above"),
+            },
+        }
+        Ok(None)
+    }
+
+    fn check_key(&self, k: &[u8]) -> Option<BencodeErrorKind> {
+        let Some(Frame::Dict { entries, .. }) = self.stack.last() else {
+            return None;
+        };
+        if entries.contains(k) {
+            return Some(BencodeErrorKind::DuplicateKey(k.to_vec()));
+        }
+        match entries.last_key() {
+            Some(last) if last > k => Some(BencodeErrorKind::UnsortedKeys(k.to_vec())),
+            _ => None,
+        }
+    }
+
+    // Fails with kind in strict mode, records it and carries on in lenient mode
+    fn warn(&mut self, kind: BencodeErrorKind, pos: usize) -> Result<(), BencodeError> {
+        let err = self.err(kind, pos);
+        if !self.options.lenient {
+            return Err(err);
+        }
+        self.warnings.push(err);
+        Ok(())
+    }
+
+    fn err(&self, kind: BencodeErrorKind, pos: usize) -> BencodeError {
+        let path = self
+            .stack
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::List(l) => Some(PathSegment::Index(l.len())),
+                Frame::Dict { key, .. } => key.clone().map(PathSegment::Key),
+            })
+            .collect();
+        BencodeError::new(kind, self.base + pos, KeyPath(path))
+    }
+}
+
+// A scalar that ran off the end of the buffer may still be completed by the next chunk,
+// as long as what is there so far could be the start of a valid token
+fn is_incomplete(err: &BencodeError, rest: &[u8]) -> bool {
+    match err.kind {
+        BencodeErrorKind::UnexpectedEof => true,
+        BencodeErrorKind::UnterminatedInt => {
+            let digits = rest[1..].strip_prefix(b"-").unwrap_or(&rest[1..]);
+            digits.iter().all(u8::is_ascii_digit)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,150 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::bencode::Bencode;
+use crate::encode;
+use crate::error::SerdeError;
+
+// JSON has no byte strings, so the conversion works like this:
+//   UTF-8 message         -> "string"
+//   any other message     -> {"$hex": "0a1b.."}
+//   integer               -> number, or {"$int": "digits"} for a BigInt
+//   non UTF-8 dict key    -> "$hex:0a1b.."
+//   dict key starting '$' -> the same key with an extra '$' in front
+//   ordered dict          -> {"$dict": [[key, value], ..]} with keys converted like messages
+// Escaping '$' keys means a real dictionary can never be mistaken for one of the tagged
+// objects, so every value converts back to the exact same bencode.
+const HEX_TAG: &str = "$hex";
+const INT_TAG: &str = "$int";
+const DICT_TAG: &str = "$dict";
+const HEX_KEY_PREFIX: &str = "$hex:";
+
+/// Converts `val` into JSON that `from_json` turns back into the same value
+pub fn to_json(val: &Bencode) -> Result<Value, SerdeError> {
+    Ok(match val {
+        Bencode::Message(m) => match std::str::from_utf8(m) {
+            Ok(s) => Value::String(s.to_string()),
+            Err(_) => tagged(HEX_TAG, hex::encode(m)),
+        },
+        Bencode::Int(i) => Value::from(*i),
+        Bencode::BigInt(s) => tagged(INT_TAG, s.clone()),
+        Bencode::List(l) => Value::Array(l.iter().map(to_json).collect::<Result<_, _>>()?),
+        Bencode::Dict(d) => {
+            let mut obj = Map::new();
+            for (k, v) in d {
+                obj.insert(json_key(k), to_json(v)?);
+            }
+            Value::Object(obj)
+        }
+        Bencode::OrderedDict(d) => {
+            let mut entries = Vec::with_capacity(d.len());
+            for (k, v) in d {
+                let key = to_json(&Bencode::Message(k.clone()))?;
+                entries.push(Value::Array(vec![key, to_json(v)?]));
+            }
+            Value::Object(Map::from_iter([(
+                DICT_TAG.to_string(),
+                Value::Array(entries),
+            )]))
+        }
+        Bencode::Stop => {
+            return Err(SerdeError::Message(
+                "Stop has no JSON representation".into(),
+            ))
+        }
+    })
+}
+
+/// Converts JSON produced by `to_json` (or written by hand in the same form) back into bencode.
+/// `true` and `false` become 1 and 0. Floats and null are rejected.
+pub fn from_json(val: &Value) -> Result<Bencode, SerdeError> {
+    Ok(match val {
+        Value::String(s) => Bencode::Message(s.as_bytes().to_vec()),
+        Value::Bool(b) => Bencode::Int(*b as i64),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => Bencode::Int(i),
+            (None, Some(u)) => Bencode::BigInt(u.to_string()),
+            _ => return Err(SerdeError::Message(format!("{n} is not an integer"))),
+        },
+        Value::Array(a) => Bencode::List(a.iter().map(from_json).collect::<Result<_, _>>()?),
+        Value::Object(obj) => match untag(obj) {
+            Some((HEX_TAG, Value::String(h))) => Bencode::Message(parse_hex(h)?),
+            Some((INT_TAG, Value::String(s))) => parse_big_int(s)?,
+            Some((DICT_TAG, Value::Array(entries))) => ordered_dict(entries)?,
+            _ => {
+                let mut d = BTreeMap::new();
+                for (k, v) in obj {
+                    d.insert(bencode_key(k)?, from_json(v)?);
+                }
+                Bencode::Dict(d)
+            }
+        },
+        Value::Null => return Err(SerdeError::Message("Bencode has no null value".into())),
+    })
+}
+
+fn tagged(tag: &str, contents: String) -> Value {
+    Value::Object(Map::from_iter([(tag.to_string(), Value::String(contents))]))
+}
+
+// Returns the tag and its contents if obj is a single entry tagged object
+fn untag(obj: &Map<String, Value>) -> Option<(&str, &Value)> {
+    if obj.len() != 1 {
+        return None;
+    }
+    let (k, v) = obj.iter().next()?;
+    match k.as_str() {
+        HEX_TAG => Some((HEX_TAG, v)),
+        INT_TAG => Some((INT_TAG, v)),
+        DICT_TAG => Some((DICT_TAG, v)),
+        _ => None,
+    }
+}
+
+fn ordered_dict(entries: &[Value]) -> Result<Bencode, SerdeError> {
+    let mut d = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let pair = match entry {
+            Value::Array(pair) if pair.len() == 2 => (from_json(&pair[0])?, from_json(&pair[1])?),
+            _ => return Err(SerdeError::Message(format!("Bad {DICT_TAG} entry {entry}"))),
+        };
+        match pair {
+            (Bencode::Message(k), v) => d.push((k, v)),
+            (k, _) => return Err(SerdeError::Message(format!("Bad {DICT_TAG} key {k:?}"))),
+        }
+    }
+    Ok(Bencode::OrderedDict(d))
+}
+
+fn json_key(k: &[u8]) -> String {
+    match std::str::from_utf8(k) {
+        Ok(s) if s.starts_with('$') => format!("${s}"),
+        Ok(s) => s.to_string(),
+        Err(_) => format!("{HEX_KEY_PREFIX}{}", hex::encode(k)),
+    }
+}
+
+fn bencode_key(k: &str) -> Result<Vec<u8>, SerdeError> {
+    if let Some(escaped) = k.strip_prefix("$$") {
+        return Ok(format!("${escaped}").into_bytes());
+    }
+    if let Some(h) = k.strip_prefix(HEX_KEY_PREFIX) {
+        return parse_hex(h);
+    }
+    Ok(k.as_bytes().to_vec())
+}
+
+fn parse_hex(h: &str) -> Result<Vec<u8>, SerdeError> {
+    hex::decode(h).map_err(|e| SerdeError::Message(format!("Bad hex string {h:?}: {e}")))
+}
+
+// Accepts any integer in canonical form, only keeping it as digits if it does not fit an i64
+fn parse_big_int(s: &str) -> Result<Bencode, SerdeError> {
+    if !encode::is_integer(s) {
+        return Err(SerdeError::Message(format!("Bad integer {s:?}")));
+    }
+    Ok(match s.parse::<i64>() {
+        Ok(i) => Bencode::Int(i),
+        Err(_) => Bencode::BigInt(s.to_string()),
+    })
+}
@@ -1,98 +1,306 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(dead_code)]
 
+extern crate alloc;
+
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "std")]
 use std::collections::BTreeMap;
+#[cfg(feature = "std")]
 use std::io::{BufRead, BufReader, BufWriter, Write};
+#[cfg(feature = "std")]
 use std::net::{Ipv4Addr, SocketAddrV4, TcpStream};
 
+#[cfg(feature = "std")]
 use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
+#[cfg(feature = "std")]
 use bencode::Bencode;
-use file_dict::FileDict;
+#[cfg(feature = "std")]
+use bencode_ref::BencodeRef;
+#[cfg(feature = "std")]
+use decode::{DecodeOptions, Decoder};
+#[cfg(feature = "std")]
+use error::{KeyPath, TypeError};
+#[cfg(feature = "std")]
+use file_dict::{FileDict, TreeFile};
+#[cfg(feature = "std")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use tracker::TrackerTiers;
 
 pub mod bencode;
+pub mod bencode_ref;
+#[cfg(feature = "std")]
+pub mod create;
+#[cfg(feature = "std")]
+mod de;
 pub mod decode;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod encode;
+pub mod error;
+#[cfg(feature = "std")]
 pub mod file_dict;
+pub mod incremental;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod magnet;
+#[cfg(feature = "std")]
+pub mod pretty;
+#[cfg(feature = "std")]
+mod ser;
+#[cfg(feature = "std")]
+pub mod tracker;
+pub mod visit;
+
+// Used by the bencode! macro, which has to name these without knowing whether std is around
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::collections::BTreeMap;
+    pub use alloc::vec::Vec;
+}
 
 // Characters that need to be escaped in hashes. Characters that are 'removed' i.e. ".-_~" are allowed (not escaped)
+#[cfg(feature = "std")]
 const ESCAPED_CHARACTERS: &AsciiSet = &NON_ALPHANUMERIC
     .remove(b'.')
     .remove(b'-')
     .remove(b'_')
     .remove(b'~');
 
+#[cfg(feature = "std")]
 pub struct MetaInfo {
-    pub announce: Vec<u8>,
-    pub announce_list: Option<Vec<Vec<u8>>>,
-    pub creation_date: Option<isize>,
+    /// None for trackerless torrents
+    pub announce: Option<Vec<u8>>,
+    /// BEP 12 tiers of tracker urls, without empty tiers. See `tracker::TrackerTiers`.
+    pub announce_list: Option<Vec<Vec<Vec<u8>>>>,
+    pub creation_date: Option<i64>,
     pub comment: Option<Vec<u8>>,
     pub created_by: Option<Vec<u8>>,
     pub encoding: Option<Vec<u8>>,
     pub url_list: Option<Vec<Vec<u8>>>,
     pub info: FileDict,
+    /// v2 `piece layers`: each file's pieces root mapped to the SHA-256 hashes of its pieces.
+    /// None for a v1 torrent.
+    pub piece_layers: Option<BTreeMap<[u8; 32], Vec<[u8; 32]>>>,
+    /// The 20 byte hash that identifies the torrent to trackers and peers. The SHA-1 of the
+    /// info dict for v1 and hybrid torrents, the truncated SHA-256 for v2 only torrents.
     pub info_hash: [u8; 20],
+    /// SHA-256 of the info dict, for torrents with `meta version` 2
+    pub info_hash_v2: Option<[u8; 32]>,
     pub escaped_hash: String,
 }
 
+#[cfg(feature = "std")]
 impl MetaInfo {
+    // Decodes a whole .torrent file. The info hash is taken over the bytes the info dict was
+    // read from, not a re-encoding of it, so it matches other clients even when the file is
+    // not canonically encoded (which lenient decoding lets through).
+    pub fn from_bytes(src: &[u8]) -> Result<MetaInfo, std::io::Error> {
+        let options = DecodeOptions {
+            lenient: true,
+            record_spans: true,
+            ..Default::default()
+        };
+        let mut decoder = Decoder::with_options(src, options);
+        let root = decoder.decode_next()?.into_owned();
+        decoder.finish()?;
+        let Bencode::Dict(root_dict) = root else {
+            return Err(make_bad_data_err(
+                "Top level bencoded value is not a dictionary",
+            ));
+        };
+        let Some(info_span) = decoder.spans().get(&KeyPath::root().key(b"info")) else {
+            return Err(make_bad_data_err("No 'info' key in torrent file"));
+        };
+        let info_bytes = &src[info_span];
+        let hashed_info = sha1_smol::Sha1::from(info_bytes).digest().bytes();
+        let is_v2 = root_dict
+            .get("info".as_bytes())
+            .and_then(|info| info.get("meta version"))
+            == Some(&Bencode::Int(2));
+        let hashed_info_v2 = is_v2.then(|| Sha256::digest(info_bytes).into());
+        Self::construct(root_dict, hashed_info, hashed_info_v2)
+    }
+
     pub fn construct_from_dict_v1(
         root_dict: BTreeMap<Vec<u8>, Bencode>,
         hashed_info: [u8; 20],
-    ) -> MetaInfo {
-        let escaped_hash = percent_encode(&hashed_info, ESCAPED_CHARACTERS).to_string();
-        MetaInfo {
-            announce: Self::get_message(&root_dict, "announce".as_bytes()).unwrap(),
-            // TODO: Implement announce-list
-            creation_date: Self::get_int(&root_dict, "creation date".as_bytes()),
-            comment: Self::get_message(&root_dict, "comment".as_bytes()),
-            created_by: Self::get_message(&root_dict, "created by".as_bytes()),
-            encoding: Self::get_message(&root_dict, "encoding".as_bytes()),
-            url_list: Self::get_url_list(&root_dict),
-            info: FileDict::construct_from_info(root_dict.get("info".as_bytes()).unwrap()),
-            announce_list: None,
-            info_hash: hashed_info,
+    ) -> Result<MetaInfo, std::io::Error> {
+        Self::construct(root_dict, hashed_info, None)
+    }
+
+    fn construct(
+        root_dict: BTreeMap<Vec<u8>, Bencode>,
+        hashed_info: [u8; 20],
+        hashed_info_v2: Option<[u8; 32]>,
+    ) -> Result<MetaInfo, std::io::Error> {
+        let info = root_dict
+            .get("info".as_bytes())
+            .ok_or_else(|| make_bad_data_err("No 'info' key"))?;
+        let info = FileDict::construct_from_info(info)?;
+        let piece_layers = match info.file_tree {
+            Some(ref tree) => Some(Self::get_piece_layers(&root_dict, tree, info.piece_length)?),
+            None => None,
+        };
+        // v2 only torrents are known by their truncated v2 hash, see BEP 52
+        let info_hash = match (&info.pieces, hashed_info_v2) {
+            (None, Some(v2)) => truncate_hash(&v2),
+            _ => hashed_info,
+        };
+        let escaped_hash = percent_encode(&info_hash, ESCAPED_CHARACTERS).to_string();
+        Ok(MetaInfo {
+            announce: Self::get_message(&root_dict, "announce")?,
+            announce_list: Self::get_announce_list(&root_dict)?,
+            creation_date: optional(&root_dict, "creation date")?,
+            comment: Self::get_message(&root_dict, "comment")?,
+            created_by: Self::get_message(&root_dict, "created by")?,
+            encoding: Self::get_message(&root_dict, "encoding")?,
+            url_list: Self::get_url_list(&root_dict)?,
+            info,
+            piece_layers,
+            info_hash,
+            info_hash_v2: hashed_info_v2,
             escaped_hash,
-        }
+        })
     }
 
-    pub fn tracker_get(meta_info: &MetaInfo, peer_id: String) -> Result<Vec<u8>, reqwest::Error> {
-        let announce_url_utf8 = std::str::from_utf8(&meta_info.announce)
-            .expect("Error converting announce url to utf-8 encoding");
-        // let bytes_left = meta.info.file_length.unwrap().to_string();
-        let res = format!(
-            "{announce_url_utf8}?\
-            info_hash={escaped_hash}&\
-            event=started&\
-            peer_id={peer_id}\
-            &compact=1\
-            &numwant=5",
-            escaped_hash = meta_info.escaped_hash
-        );
-        Ok(reqwest::blocking::get(res)?
-            .bytes()
-            .unwrap()
-            .iter()
-            .copied()
-            .collect())
+    /// First 20 bytes of the v2 info hash, which v2 aware peers and trackers accept in place
+    /// of a SHA-1 info hash
+    pub fn truncated_info_hash_v2(&self) -> Option<[u8; 20]> {
+        self.info_hash_v2.as_ref().map(truncate_hash)
     }
 
-    fn get_message(d: &BTreeMap<Vec<u8>, Bencode>, key: &[u8]) -> Option<Vec<u8>> {
-        d.get(key).map(|b| b.unwrap_message())
+    /// SHA-1 of the info dict, None for v2 only torrents which have no v1 pieces to check
+    pub fn info_hash_v1(&self) -> Option<[u8; 20]> {
+        self.info.pieces.is_some().then_some(self.info_hash)
     }
 
-    fn get_int(d: &BTreeMap<Vec<u8>, Bencode>, key: &[u8]) -> Option<isize> {
-        d.get(key).map(|b| b.unwrap_int())
+    /// Has both v1 pieces and a v2 file tree, so it can be shared in either swarm
+    pub fn is_hybrid(&self) -> bool {
+        self.info_hash_v1().is_some() && self.info_hash_v2.is_some()
     }
 
-    fn get_url_list(d: &BTreeMap<Vec<u8>, Bencode>) -> Option<Vec<Vec<u8>>> {
-        d.get("url-list".as_bytes()).map(|b| {
-            b.unwrap_list()
-                .iter()
-                .map(|url| url.unwrap_message())
-                .collect()
-        })
+    /// The 20 byte hashes of every swarm the torrent belongs to: the SHA-1 hash, the truncated
+    /// v2 hash, or both for a hybrid torrent. Each is announced and handshaken on its own.
+    pub fn swarm_hashes(&self) -> Vec<[u8; 20]> {
+        self.info_hash_v1()
+            .into_iter()
+            .chain(self.truncated_info_hash_v2())
+            .collect()
+    }
+
+    // Announces to the trackers in BEP 12 order and returns the first response
+    pub fn tracker_get(meta_info: &MetaInfo, peer_id: String) -> Result<Vec<u8>, Box<dyn Error>> {
+        let Some(mut tiers) = TrackerTiers::new(meta_info) else {
+            return Err("Torrent has no trackers".into());
+        };
+        Self::tracker_get_with_hash(&mut tiers, &meta_info.info_hash, &peer_id)
+    }
+
+    // Like tracker_get for one of the swarm_hashes, with the torrent's tiers passed in so the
+    // tracker that answered is tried first on the next announce
+    pub fn tracker_get_with_hash(
+        tiers: &mut TrackerTiers,
+        info_hash: &[u8; 20],
+        peer_id: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(tiers.announce(|url| Self::announce_to(url, info_hash, peer_id))?)
+    }
+
+    // Sends a started event to the tracker at url. Error statuses count as failures.
+    pub fn announce_to(
+        url: &[u8],
+        info_hash: &[u8; 20],
+        peer_id: &str,
+    ) -> Result<Vec<u8>, reqwest::Error> {
+        Ok(
+            reqwest::blocking::get(announce_url(url, info_hash, peer_id))?
+                .error_for_status()?
+                .bytes()?
+                .to_vec(),
+        )
+    }
+
+    fn get_message(
+        d: &BTreeMap<Vec<u8>, Bencode>,
+        key: &str,
+    ) -> Result<Option<Vec<u8>>, std::io::Error> {
+        Ok(optional::<&[u8]>(d, key)?.map(<[u8]>::to_vec))
+    }
+
+    // Every file bigger than one piece needs a layer with one hash per piece. Smaller files
+    // only have their pieces root, which is then the hash of their single piece.
+    fn get_piece_layers(
+        d: &BTreeMap<Vec<u8>, Bencode>,
+        tree: &[TreeFile],
+        piece_length: i64,
+    ) -> Result<BTreeMap<[u8; 32], Vec<[u8; 32]>>, std::io::Error> {
+        let layers: &BTreeMap<Vec<u8>, Bencode> = required(d, "piece layers")?;
+        let mut res = BTreeMap::new();
+        for (root, layer) in layers {
+            let root = <[u8; 32]>::try_from(root.as_slice())
+                .map_err(|_| make_bad_data_err("Key in piece layers is not 32 bytes"))?;
+            let layer = <&[u8]>::try_from(layer)
+                .map_err(|e| make_bad_data_err(&format!("'piece layers': {e}")))?;
+            if !layer.len().is_multiple_of(32) {
+                return Err(make_bad_data_err("Piece layer is not a multiple of 32"));
+            }
+            let hashes: Vec<[u8; 32]> = layer
+                .chunks_exact(32)
+                .map(|hash| vec_to_array(hash.to_vec()))
+                .collect();
+            res.insert(root, hashes);
+        }
+        for file in tree.iter().filter(|file| file.length > piece_length) {
+            let pieces = (file.length as u64).div_ceil(piece_length as u64) as usize;
+            let root = file
+                .pieces_root
+                .expect("files with data have a pieces root");
+            match res.get(&root) {
+                Some(layer) if layer.len() == pieces => {}
+                Some(_) => return Err(make_bad_data_err("Piece layer has the wrong length")),
+                None => return Err(make_bad_data_err("File has no piece layer")),
+            }
+        }
+        Ok(res)
+    }
+
+    // A list of tiers, each a list of urls
+    fn get_announce_list(
+        d: &BTreeMap<Vec<u8>, Bencode>,
+    ) -> Result<Option<Vec<Vec<Vec<u8>>>>, std::io::Error> {
+        let Some(list) = optional::<&[Bencode]>(d, "announce-list")? else {
+            return Ok(None);
+        };
+        let mut tiers = Vec::with_capacity(list.len());
+        for tier in list {
+            let urls = message_list(tier, "announce-list")?;
+            if !urls.is_empty() {
+                tiers.push(urls);
+            }
+        }
+        Ok(Some(tiers))
+    }
+
+    // BEP 19 allows a single url in place of the list
+    fn get_url_list(
+        d: &BTreeMap<Vec<u8>, Bencode>,
+    ) -> Result<Option<Vec<Vec<u8>>>, std::io::Error> {
+        match d.get("url-list".as_bytes()) {
+            None => Ok(None),
+            Some(Bencode::Message(url)) => Ok(Some(vec![url.clone()])),
+            Some(list) => message_list(list, "url-list").map(Some),
+        }
     }
 }
 
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Peer {
     pub am_choking: u8,
@@ -106,6 +314,7 @@ pub struct Peer {
     pub bitfield: Vec<u8>,
 }
 
+#[cfg(feature = "std")]
 impl Peer {
     pub fn new_peer(socket: SocketAddrV4) -> Option<Peer> {
         let read_handle = match TcpStream::connect_timeout(
@@ -133,13 +342,14 @@ impl Peer {
     }
 
     pub fn get_peers(response: Vec<u8>) -> Result<Vec<Peer>, std::io::Error> {
-        let mut response_iter = response.iter().peekable();
-        let bencoded_response = Bencode::decode_dispatch(&mut response_iter)?;
-        let tracker_response_dict = bencoded_response.unwrap_dict();
-        if let Some(x) = tracker_response_dict.get("failure reason".as_bytes()) {
+        let bencoded_response = BencodeRef::decode(&response)?.into_owned();
+        let Some(tracker_response_dict) = bencoded_response.as_dict() else {
+            return Err(make_bad_data_err("Tracker response is not a dictionary"));
+        };
+        if let Some(reason) = optional::<&[u8]>(tracker_response_dict, "failure reason")? {
             return Err(make_bad_data_err(&format!(
                 "Tracker Request Failed with reason: \n{}",
-                escape_u8_slice(&x.unwrap_message())
+                escape_u8_slice(reason)
             )));
         }
         match tracker_response_dict.get("peers".as_bytes()) {
@@ -153,7 +363,7 @@ impl Peer {
     }
 
     pub fn deserialize_compact_peers(bytes: Vec<u8>) -> Result<Vec<Peer>, std::io::Error> {
-        if bytes.len() % 6 != 0 {
+        if !bytes.len().is_multiple_of(6) {
             return Err(make_bad_data_err(
                 "Comapct peers byte string is not a multiple of 6. Impossible to parse",
             ));
@@ -215,21 +425,79 @@ impl Peer {
     }
 }
 
-// Helper method to shorten a throwing of an InvalidData error. TODO: Change to a macro 
+// Helper method to shorten a throwing of an InvalidData error. TODO: Change to a macro
+#[cfg(feature = "std")]
 pub fn make_bad_data_err(err_msg: &str) -> std::io::Error {
     std::io::Error::new(std::io::ErrorKind::InvalidData, err_msg)
 }
 
-//Helper method that '\' escapes whitespaces and '\xx' escapes other non-printables  
+// Reads the entry under key, failing with an error naming the key if it is missing or has the wrong type
+#[cfg(feature = "std")]
+pub(crate) fn required<'a, T>(d: &'a BTreeMap<Vec<u8>, Bencode>, key: &str) -> std::io::Result<T>
+where
+    T: TryFrom<&'a Bencode, Error = TypeError>,
+{
+    optional(d, key)?.ok_or_else(|| make_bad_data_err(&format!("No '{key}' key")))
+}
+
+// Like required, but a missing key is Ok(None)
+#[cfg(feature = "std")]
+pub(crate) fn optional<'a, T>(
+    d: &'a BTreeMap<Vec<u8>, Bencode>,
+    key: &str,
+) -> std::io::Result<Option<T>>
+where
+    T: TryFrom<&'a Bencode, Error = TypeError>,
+{
+    d.get(key.as_bytes())
+        .map(|val| T::try_from(val).map_err(|e| make_bad_data_err(&format!("'{key}': {e}"))))
+        .transpose()
+}
+
+// Copies out a list of messages such as a file path or url-list. what names it in errors.
+#[cfg(feature = "std")]
+pub(crate) fn message_list(val: &Bencode, what: &str) -> std::io::Result<Vec<Vec<u8>>> {
+    let bad = |e: TypeError| make_bad_data_err(&format!("'{what}': {e}"));
+    <&[Bencode]>::try_from(val)
+        .map_err(bad)?
+        .iter()
+        .map(|m| <&[u8]>::try_from(m).map(<[u8]>::to_vec).map_err(bad))
+        .collect()
+}
+
+//Helper method that '\' escapes whitespaces and '\xx' escapes other non-printables
 pub fn escape_u8_slice(src: &[u8]) -> String {
     String::from_utf8(
         src.iter()
-            .flat_map(|b| std::ascii::escape_default(*b))
+            .flat_map(|b| core::ascii::escape_default(*b))
             .collect::<Vec<u8>>(),
     )
     .unwrap()
 }
 
+// Url of a started announce for info_hash to the tracker at url
+#[cfg(feature = "std")]
+pub fn announce_url(url: &[u8], info_hash: &[u8; 20], peer_id: &str) -> String {
+    // Tracker urls are ascii in practice, the lossy conversion only touches broken ones
+    let announce_url_utf8 = String::from_utf8_lossy(url);
+    // let bytes_left = meta.info.file_length.unwrap().to_string();
+    format!(
+        "{announce_url_utf8}?\
+        info_hash={escaped_hash}&\
+        event=started&\
+        peer_id={peer_id}\
+        &compact=1\
+        &numwant=5",
+        escaped_hash = percent_encode(info_hash, ESCAPED_CHARACTERS)
+    )
+}
+
+// The 20 byte form of a v2 info hash used by the v1 tracker and peer protocols
+#[cfg(feature = "std")]
+fn truncate_hash(hash: &[u8; 32]) -> [u8; 20] {
+    vec_to_array(hash[..20].to_vec())
+}
+
 // Helper method to make a fixed size array of size N from a vector of size N.
 // Used because certain reader methods require an array as a buffer to be filled
 pub fn vec_to_array<T, const N: usize>(v: Vec<T>) -> [T; N] {
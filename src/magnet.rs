@@ -0,0 +1,202 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::ops::RangeInclusive;
+
+use data_encoding::BASE32;
+use percent_encoding::{percent_decode_str, percent_encode};
+
+use crate::{make_bad_data_err, truncate_hash, MetaInfo, ESCAPED_CHARACTERS};
+
+// Multihash prefix of a SHA-256 digest: function code 0x12, 32 bytes of digest
+const SHA256_MULTIHASH: [u8; 2] = [0x12, 0x20];
+
+/// A BEP 9 magnet link, which names a torrent by its info hash so the info dict can be
+/// fetched from peers. At least one of the two hashes is always set. Parse one with
+/// `MagnetLink::parse` and turn it back into a link with `to_string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MagnetLink {
+    /// `xt=urn:btih:`, the v1 info hash
+    pub info_hash: Option<[u8; 20]>,
+    /// `xt=urn:btmh:`, the SHA-256 info hash of a v2 or hybrid torrent
+    pub info_hash_v2: Option<[u8; 32]>,
+    /// `dn`, a name to show until the info dict arrives
+    pub display_name: Option<String>,
+    /// `tr`, tracker urls in the order they were given
+    pub trackers: Vec<String>,
+    /// `ws`, BEP 19 web seeds
+    pub web_seeds: Vec<String>,
+    /// `x.pe`, peers to try first, as host:port
+    pub peers: Vec<String>,
+    /// `so`, BEP 53 indices of the files to download. None for all of them.
+    pub select_only: Option<Vec<RangeInclusive<usize>>>,
+}
+
+impl MagnetLink {
+    /// Parses a `magnet:?` link. Parameters other than the ones above, and exact topics
+    /// other than btih and btmh, are ignored.
+    pub fn parse(link: &str) -> io::Result<MagnetLink> {
+        let query = link
+            .strip_prefix("magnet:?")
+            .ok_or_else(|| make_bad_data_err("Not a magnet link"))?;
+        let mut magnet = MagnetLink {
+            info_hash: None,
+            info_hash_v2: None,
+            display_name: None,
+            trackers: Vec::new(),
+            web_seeds: Vec::new(),
+            peers: Vec::new(),
+            select_only: None,
+        };
+        for param in query.split('&').filter(|param| !param.is_empty()) {
+            let (key, value) = param.split_once('=').ok_or_else(|| {
+                make_bad_data_err(&format!("Magnet parameter {param} has no value"))
+            })?;
+            // Repeated parameters may be numbered, as in tr.1 and tr.2
+            let key = match key.rsplit_once('.') {
+                Some((key, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => key,
+                _ => key,
+            };
+            // Some clients write spaces in the name as '+'. In urls it is a plain '+'.
+            let value = match key {
+                "dn" => unescape(&value.replace('+', " "))?,
+                _ => unescape(value)?,
+            };
+            match key {
+                "xt" => magnet.exact_topic(&value)?,
+                "dn" => magnet.display_name = Some(value),
+                "tr" => magnet.trackers.push(value),
+                "ws" => magnet.web_seeds.push(value),
+                "x.pe" => magnet.peers.push(value),
+                "so" => magnet.select_only = Some(parse_select_only(&value)?),
+                _ => {}
+            }
+        }
+        if magnet.info_hash.is_none() && magnet.info_hash_v2.is_none() {
+            return Err(make_bad_data_err("Magnet link has no BitTorrent info hash"));
+        }
+        Ok(magnet)
+    }
+
+    /// Link to a torrent with its name, every tracker and its web seeds
+    pub fn from_meta_info(meta_info: &MetaInfo) -> MagnetLink {
+        let trackers = match (&meta_info.announce_list, &meta_info.announce) {
+            (Some(list), _) if !list.is_empty() => list.iter().flatten().collect(),
+            (_, Some(announce)) => vec![announce],
+            _ => Vec::new(),
+        };
+        let lossy = |s: &Vec<u8>| String::from_utf8_lossy(s).into_owned();
+        MagnetLink {
+            info_hash: meta_info.info_hash_v1(),
+            info_hash_v2: meta_info.info_hash_v2,
+            display_name: Some(lossy(&meta_info.info.name)),
+            trackers: trackers.into_iter().map(lossy).collect(),
+            web_seeds: meta_info.url_list.iter().flatten().map(lossy).collect(),
+            peers: Vec::new(),
+            select_only: None,
+        }
+    }
+
+    /// The 20 byte hashes to look for peers with, like `MetaInfo::swarm_hashes`
+    pub fn swarm_hashes(&self) -> Vec<[u8; 20]> {
+        self.info_hash
+            .into_iter()
+            .chain(self.info_hash_v2.as_ref().map(truncate_hash))
+            .collect()
+    }
+
+    fn exact_topic(&mut self, urn: &str) -> io::Result<()> {
+        if let Some(hash) = urn.strip_prefix("urn:btih:") {
+            // 40 hex digits, or 32 base32 characters in older links
+            let bytes = match hash.len() {
+                40 => hex::decode(hash).ok(),
+                32 => BASE32.decode(hash.to_ascii_uppercase().as_bytes()).ok(),
+                _ => None,
+            };
+            let hash = bytes
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| make_bad_data_err(&format!("Invalid btih info hash {hash}")))?;
+            set_once(&mut self.info_hash, hash)
+        } else if let Some(multihash) = urn.strip_prefix("urn:btmh:") {
+            let bytes = hex::decode(multihash).unwrap_or_default();
+            let hash = bytes
+                .strip_prefix(&SHA256_MULTIHASH)
+                .and_then(|digest| digest.try_into().ok())
+                .ok_or_else(|| make_bad_data_err(&format!("Invalid btmh info hash {multihash}")))?;
+            set_once(&mut self.info_hash_v2, hash)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// Two different hashes of the same kind can't name one torrent
+fn set_once<const N: usize>(slot: &mut Option<[u8; N]>, hash: [u8; N]) -> io::Result<()> {
+    match slot {
+        Some(old) if *old != hash => {
+            Err(make_bad_data_err("Magnet link has conflicting info hashes"))
+        }
+        _ => {
+            *slot = Some(hash);
+            Ok(())
+        }
+    }
+}
+
+// Values are percent-encoded
+fn unescape(value: &str) -> io::Result<String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .map(|value| value.into_owned())
+        .map_err(|_| make_bad_data_err("Magnet parameter is not valid UTF-8"))
+}
+
+// so=0,2,4-6
+fn parse_select_only(value: &str) -> io::Result<Vec<RangeInclusive<usize>>> {
+    let invalid = || make_bad_data_err(&format!("Invalid file selection {value}"));
+    value
+        .split(',')
+        .map(|item| {
+            let (start, end) = item.split_once('-').unwrap_or((item, item));
+            let start: usize = start.parse().map_err(|_| invalid())?;
+            let end: usize = end.parse().map_err(|_| invalid())?;
+            match start <= end {
+                true => Ok(start..=end),
+                false => Err(invalid()),
+            }
+        })
+        .collect()
+}
+
+impl Display for MagnetLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = Vec::new();
+        if let Some(hash) = &self.info_hash {
+            params.push(format!("xt=urn:btih:{}", hex::encode(hash)));
+        }
+        if let Some(hash) = &self.info_hash_v2 {
+            let multihash = [&SHA256_MULTIHASH[..], hash].concat();
+            params.push(format!("xt=urn:btmh:{}", hex::encode(multihash)));
+        }
+        let escaped = |key: &str, value: &str| {
+            format!(
+                "{key}={}",
+                percent_encode(value.as_bytes(), ESCAPED_CHARACTERS)
+            )
+        };
+        params.extend(self.display_name.iter().map(|name| escaped("dn", name)));
+        params.extend(self.trackers.iter().map(|url| escaped("tr", url)));
+        params.extend(self.web_seeds.iter().map(|url| escaped("ws", url)));
+        params.extend(self.peers.iter().map(|peer| escaped("x.pe", peer)));
+        if let Some(ranges) = &self.select_only {
+            let ranges: Vec<String> = ranges
+                .iter()
+                .map(|range| match range.start() == range.end() {
+                    true => range.start().to_string(),
+                    false => format!("{}-{}", range.start(), range.end()),
+                })
+                .collect();
+            params.push(format!("so={}", ranges.join(",")));
+        }
+        write!(f, "magnet:?{}", params.join("&"))
+    }
+}
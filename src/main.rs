@@ -1,29 +1,74 @@
-use bit_tor::{bencode::Bencode, escape_u8_slice, vec_to_array, MetaInfo, Peer};
+use bit_tor::bencode::{self, Bencode};
+use bit_tor::create::TorrentBuilder;
+use bit_tor::decode::{DecodeOptions, Decoder};
+use bit_tor::diff::Difference;
+use bit_tor::error::KeyPath;
+use bit_tor::json;
+use bit_tor::pretty::PrettyOptions;
+use bit_tor::tracker::TrackerTiers;
+use bit_tor::{escape_u8_slice, vec_to_array, MetaInfo, Peer};
 
-use core::panic;
-use std::collections::BTreeMap;
 use std::error::Error;
 use std::io::prelude::*;
-use std::{env, fs};
+use std::io::IsTerminal;
+use std::{env, fs, io};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
-    let file = fs::File::open(
-        args.get(1)
-            .expect("No file supplied in command line invocation"),
-    )?;
+    match args.get(1).map(String::as_str) {
+        Some("bencode") => return bencode_command(&args[2..]),
+        // Exit status as in diff(1): 0 for the same, 1 for different, 2 for trouble
+        Some("diff") => match diff_command(&args[2..]) {
+            Ok(differ) => std::process::exit(differ as i32),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(2);
+            }
+        },
+        Some("dump") => return dump_command(&args[2..]),
+        Some("create") => return create_command(&args[2..]),
+        _ => {}
+    }
+    let path = args
+        .get(1)
+        .expect("No file supplied in command line invocation");
     let peer_id = make_peer_id();
-    let root_dict = read_torrent(file)?;
-    let info_bencoded = bencode_info(&root_dict);
-    let hashed_info = sha1_smol::Sha1::from(info_bencoded).digest().bytes();
-    let meta_info = MetaInfo::construct_from_dict_v1(root_dict, hashed_info);
-    let response = MetaInfo::tracker_get(&meta_info, peer_id)?;
+    let meta_info = read_torrent(path)?;
+    let Some(mut tiers) = TrackerTiers::new(&meta_info) else {
+        return Err("Torrent has no trackers".into());
+    };
+    // A hybrid torrent is in a v1 and a v2 swarm, each known by its own hash. One swarm
+    // failing doesn't stop us from trying the other, only all of them failing is an error.
+    let mut last_err = None;
+    let mut joined = false;
+    for info_hash in meta_info.swarm_hashes() {
+        match join_swarm(&meta_info, &mut tiers, &info_hash, &peer_id) {
+            Ok(()) => joined = true,
+            Err(e) => {
+                eprintln!("Swarm {} failed: {e}", hex::encode(info_hash));
+                last_err = Some(e);
+            }
+        }
+    }
+    match (joined, last_err) {
+        (false, Some(e)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn join_swarm(
+    meta_info: &MetaInfo,
+    tiers: &mut TrackerTiers,
+    info_hash: &[u8; 20],
+    peer_id: &str,
+) -> Result<(), Box<dyn Error>> {
+    let response = MetaInfo::tracker_get_with_hash(tiers, info_hash, peer_id)?;
     println!("TRACKER RESPONSE: {}", escape_u8_slice(&response));
     let mut peers = Peer::get_peers(response)?;
     peers
         .iter()
         .for_each(|p| println!("Socket: {:?}", p.socket));
-    let handshake = serialize_handshake(&meta_info, make_peer_id());
+    let handshake = serialize_handshake(meta_info, info_hash, make_peer_id());
     for peer in peers.iter_mut() {
         peer.write_to_peer(handshake.as_slice())?;
     }
@@ -31,19 +76,206 @@ fn main() -> Result<(), Box<dyn Error>> {
     dbg!("After Writes");
     // Drop peers with bad info hash
     peers.retain_mut(|peer: &mut Peer| {
-        let peers_info_hash = read_handshake(peer).unwrap_or(Vec::new());
-        hashed_info[..] == peers_info_hash
+        let peers_info_hash = read_handshake(peer).unwrap_or_default();
+        info_hash[..] == peers_info_hash
     });
 
     Ok(())
 }
 
-fn bencode_info(root_dict: &BTreeMap<Vec<u8>, Bencode>) -> Vec<u8> {
-    let info_bencoded = root_dict
-        .get("info".as_bytes())
-        .expect("No 'info' key in torrent file")
-        .encode_val();
-    info_bencoded
+const BENCODE_USAGE: &str = "Usage: bit_tor bencode to-json [file]
+       bit_tor bencode from-json [file]
+       bit_tor bencode get <file> <path>";
+
+// bit_tor bencode <command> [file] [args]
+// Reads file, or stdin if it is missing or "-", and writes the result to stdout
+fn bencode_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut out = io::stdout().lock();
+    match args.first().map(String::as_str) {
+        Some("to-json") => {
+            let val = read_bencode(args.get(1))?;
+            serde_json::to_writer_pretty(&mut out, &json::to_json(&val)?)?;
+            writeln!(out)?;
+        }
+        Some("from-json") => {
+            let val: serde_json::Value = serde_json::from_slice(&read_input(args.get(1))?)?;
+            json::from_json(&val)?.encode_to(&mut out)?;
+        }
+        Some("get") => {
+            let (Some(file), Some(path)) = (args.get(1), args.get(2)) else {
+                return Err(BENCODE_USAGE.into());
+            };
+            let val = read_bencode(Some(file))?;
+            let found = val
+                .pointer(path)
+                .ok_or_else(|| format!("Nothing at {path}"))?;
+            // Plain strings and numbers are printed as is, anything else as JSON
+            match found {
+                Bencode::Message(m) if std::str::from_utf8(m).is_ok() => out.write_all(m)?,
+                Bencode::Int(i) => write!(out, "{i}")?,
+                Bencode::BigInt(s) => write!(out, "{s}")?,
+                other => serde_json::to_writer_pretty(&mut out, &json::to_json(other)?)?,
+            }
+            writeln!(out)?;
+        }
+        _ => return Err(BENCODE_USAGE.into()),
+    }
+    Ok(())
+}
+
+// Integers of any size are accepted, so nothing is lost on the way to JSON
+fn read_bencode(path: Option<&String>) -> io::Result<Bencode> {
+    let options = DecodeOptions {
+        big_ints: true,
+        ..Default::default()
+    };
+    match path.map(String::as_str) {
+        None | Some("-") => Bencode::from_reader_with_options(io::stdin().lock(), options),
+        Some(path) => Bencode::from_reader_with_options(fs::File::open(path)?, options),
+    }
+}
+
+const DUMP_USAGE: &str = "Usage: bit_tor dump [--colour | --no-colour] [--full] [file]";
+
+// bit_tor dump [options] [file]
+// Pretty prints a torrent, tracker response or any other bencode. Colour defaults to on for a
+// terminal and --full prints long strings instead of their SHA-1. Input is decoded leniently
+// with dictionaries in file order, so broken files still print, and each problem found is
+// reported on stderr.
+fn dump_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut options = PrettyOptions {
+        colour: io::stdout().is_terminal(),
+        ..Default::default()
+    };
+    let mut file = None;
+    for arg in args {
+        match arg.as_str() {
+            "--colour" | "--color" => options.colour = true,
+            "--no-colour" | "--no-color" => options.colour = false,
+            "--full" => options.max_string_len = usize::MAX,
+            flag if flag.starts_with("--") => return Err(DUMP_USAGE.into()),
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err(DUMP_USAGE.into()),
+        }
+    }
+    let decode_options = DecodeOptions {
+        lenient: true,
+        big_ints: true,
+        preserve_order: true,
+        ..Default::default()
+    };
+    let src = read_input(file)?;
+    let mut decoder = Decoder::with_options(&src, decode_options);
+    let val = decoder.decode_next()?.into_owned();
+    decoder.finish()?;
+    for warning in decoder.warnings() {
+        eprintln!("warning: {warning}");
+    }
+    println!("{}", val.pretty_with(options));
+    Ok(())
+}
+
+// bit_tor diff <old> <new>
+// Prints one line per difference and returns whether there were any
+fn diff_command(args: &[String]) -> Result<bool, Box<dyn Error>> {
+    let [old, new] = args else {
+        return Err("Usage: bit_tor diff <old.torrent> <new.torrent>".into());
+    };
+    let old_src = read_input(Some(old))?;
+    let new_src = read_input(Some(new))?;
+    let (old_val, old_info) = decode_with_info(&old_src)?;
+    let (new_val, new_info) = decode_with_info(&new_src)?;
+    let differences = bencode::diff(&old_val, &new_val);
+    for d in &differences {
+        println!("{d}");
+    }
+    // The info hash is taken over the raw bytes, so the same values encoded differently
+    // (unsorted keys, padded numbers) still change it
+    let info_reencoded = old_info != new_info;
+    if differences.iter().any(Difference::in_info) {
+        println!("The info dictionaries differ, so the info hashes differ");
+    } else if info_reencoded {
+        println!("The info dictionaries hold the same values but are encoded differently, so the info hashes differ");
+    } else if !differences.is_empty() {
+        println!("Only fields outside the info dictionary differ, the info hashes match");
+    }
+    Ok(!differences.is_empty() || info_reencoded)
+}
+
+const CREATE_USAGE: &str = "Usage: bit_tor create <path> [-o out.torrent] [--piece-length N] \
+[--tracker URL]... [--tier URL,URL...]... [--web-seed URL]... [--comment TEXT] [--private] \
+[--no-date]";
+
+// bit_tor create <path> [options]
+// Writes a torrent of a file or directory to <name>.torrent unless -o says otherwise, and
+// prints its info hash
+fn create_command(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut args = args.iter();
+    let mut path = None;
+    let mut out = None;
+    let mut builder_args = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--private" | "--no-date" => builder_args.push((arg.as_str(), "")),
+            "-o" | "--piece-length" | "--tracker" | "--tier" | "--web-seed" | "--comment" => {
+                let value = args.next().ok_or(CREATE_USAGE)?;
+                match arg.as_str() {
+                    "-o" => out = Some(value.clone()),
+                    _ => builder_args.push((arg.as_str(), value.as_str())),
+                }
+            }
+            flag if flag.starts_with('-') => return Err(CREATE_USAGE.into()),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(CREATE_USAGE.into()),
+        }
+    }
+    let path = path.ok_or(CREATE_USAGE)?;
+    let mut builder = TorrentBuilder::new(path);
+    for (flag, value) in builder_args {
+        builder = match flag {
+            "--private" => builder.private(true),
+            "--no-date" => builder.creation_date(None),
+            "--piece-length" => builder.piece_length(value.parse()?),
+            "--tracker" => builder.tracker(value),
+            "--tier" => builder.tier(value.split(',').map(String::from).collect()),
+            "--web-seed" => builder.web_seed(value),
+            _ => builder.comment(value),
+        };
+    }
+    let torrent = builder.to_bytes()?;
+    let meta_info = MetaInfo::from_bytes(&torrent)?;
+    let out = match out {
+        Some(out) => out,
+        None => format!("{}.torrent", String::from_utf8_lossy(&meta_info.info.name)),
+    };
+    fs::write(&out, &torrent)?;
+    println!("Wrote {out}");
+    println!("Info hash: {}", hex::encode(meta_info.info_hash));
+    Ok(())
+}
+
+// Decodes a whole document leniently and returns it with the raw bytes of its info dict, if any
+fn decode_with_info(src: &[u8]) -> io::Result<(Bencode, Option<&[u8]>)> {
+    let options = DecodeOptions {
+        lenient: true,
+        big_ints: true,
+        record_spans: true,
+        ..Default::default()
+    };
+    let mut decoder = Decoder::with_options(src, options);
+    let val = decoder.decode_next()?.into_owned();
+    decoder.finish()?;
+    let info = decoder.spans().get(&KeyPath::root().key(b"info"));
+    Ok((val, info.map(|span| &src[span])))
+}
+
+fn read_input(path: Option<&String>) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    match path.map(String::as_str) {
+        None | Some("-") => io::stdin().read_to_end(&mut buf)?,
+        Some(path) => fs::File::open(path)?.read_to_end(&mut buf)?,
+    };
+    Ok(buf)
 }
 
 //  Handshake Structure:
@@ -58,14 +290,8 @@ fn read_handshake(peer: &mut Peer) -> std::io::Result<Vec<u8>> {
 }
 
 // Read .torrent file and de-bencode it, First value in a .torrent should be a bencoded dictionary.
-fn read_torrent(mut file: fs::File) -> Result<BTreeMap<Vec<u8>, Bencode>, std::io::Error> {
-    let mut buf = Vec::with_capacity(1_000_000);
-    let _bytes_read = file.read_to_end(&mut buf);
-    let root_dict = match Bencode::decode_dispatch(&mut buf.iter().peekable())? {
-        Bencode::Dict(d) => d,
-        _ => panic!("Top level bencoded value is not a dictionary"),
-    };
-    Ok(root_dict)
+fn read_torrent(path: &str) -> Result<MetaInfo, std::io::Error> {
+    MetaInfo::from_bytes(&fs::read(path)?)
 }
 
 // Generates a peer id in the Azureus-style described here: https://wiki.theory.org/BitTorrentSpecification#peer_id
@@ -85,14 +311,19 @@ fn make_peer_id() -> String {
 // Construct handshake byte string to send to peers
 //len(info_hash) + len(peer_id) + 8 reserved bytes + 1 (byte declaring length of protocol string)
 const BASE_HANDSHAKE_LENGTH: usize = 20 + 20 + 8 + 1;
-fn serialize_handshake(meta: &MetaInfo, peer_id: String) -> Vec<u8> {
+fn serialize_handshake(meta: &MetaInfo, info_hash: &[u8; 20], peer_id: String) -> Vec<u8> {
     let pstr = "BitTorrent protocol".as_bytes();
     let pstr_len = pstr.len();
     let mut raw_bytes = Vec::<u8>::with_capacity(BASE_HANDSHAKE_LENGTH + pstr_len);
     raw_bytes.push(pstr_len as u8);
     raw_bytes.extend(pstr);
-    raw_bytes.extend([0u8; 8]); //Reserved Bytes
-    raw_bytes.extend(meta.info_hash);
+    let mut reserved = [0u8; 8];
+    // BEP 52: tells peers in the v1 swarm of a hybrid torrent that we can switch to v2
+    if meta.info_hash_v2.is_some() {
+        reserved[7] |= 0x10;
+    }
+    raw_bytes.extend(reserved);
+    raw_bytes.extend(info_hash);
     raw_bytes.extend(peer_id.as_bytes());
     raw_bytes
 }
@@ -0,0 +1,141 @@
+use std::fmt::{self, Display};
+
+use crate::bencode::Bencode;
+
+/// How `Pretty` lays out a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrettyOptions {
+    /// Spaces per level of nesting
+    pub indent: usize,
+    /// Strings longer than this many bytes are shown as their length and SHA-1 instead
+    pub max_string_len: usize,
+    /// Colour keys, strings, numbers and summaries with ANSI escape codes
+    pub colour: bool,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions {
+            indent: 2,
+            max_string_len: 128,
+            colour: false,
+        }
+    }
+}
+
+/// Indented, human readable rendering of a `Bencode` value, see `Bencode::pretty`.
+/// UTF-8 strings are quoted and escaped, other strings are written as `<hex ..>`, and
+/// strings past `max_string_len` (such as `pieces`) become `<6840 bytes, sha1=..>`.
+pub struct Pretty<'a> {
+    val: &'a Bencode,
+    options: PrettyOptions,
+}
+
+const KEY: &str = "\x1b[34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[33m";
+const BINARY: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+impl Bencode {
+    /// Renders the value with the default `PrettyOptions` when formatted with `{}`
+    pub fn pretty(&self) -> Pretty<'_> {
+        self.pretty_with(PrettyOptions::default())
+    }
+
+    pub fn pretty_with(&self, options: PrettyOptions) -> Pretty<'_> {
+        Pretty { val: self, options }
+    }
+}
+
+impl Pretty<'_> {
+    fn write_value(&self, f: &mut fmt::Formatter<'_>, val: &Bencode, depth: usize) -> fmt::Result {
+        match val {
+            Bencode::Message(m) => self.write_string(f, m, STRING),
+            Bencode::Int(i) => self.paint(f, NUMBER, i),
+            Bencode::BigInt(s) => self.paint(f, NUMBER, s),
+            Bencode::List(l) if l.is_empty() => write!(f, "[]"),
+            Bencode::List(l) => {
+                writeln!(f, "[")?;
+                for (i, item) in l.iter().enumerate() {
+                    self.write_indent(f, depth + 1)?;
+                    self.write_value(f, item, depth + 1)?;
+                    self.end_line(f, i + 1 < l.len())?;
+                }
+                self.write_indent(f, depth)?;
+                write!(f, "]")
+            }
+            Bencode::Dict(d) => self.write_entries(f, d.iter(), d.len(), depth),
+            Bencode::OrderedDict(d) => {
+                self.write_entries(f, d.iter().map(|(k, v)| (k, v)), d.len(), depth)
+            }
+            Bencode::Stop => write!(f, "<stop>"),
+        }
+    }
+
+    fn write_entries<'v>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        entries: impl Iterator<Item = (&'v Vec<u8>, &'v Bencode)>,
+        len: usize,
+        depth: usize,
+    ) -> fmt::Result {
+        if len == 0 {
+            return write!(f, "{{}}");
+        }
+        writeln!(f, "{{")?;
+        for (i, (k, v)) in entries.enumerate() {
+            self.write_indent(f, depth + 1)?;
+            self.write_string(f, k, KEY)?;
+            write!(f, ": ")?;
+            self.write_value(f, v, depth + 1)?;
+            self.end_line(f, i + 1 < len)?;
+        }
+        self.write_indent(f, depth)?;
+        write!(f, "}}")
+    }
+
+    // Text gets colour, anything that is not shown as text gets BINARY
+    fn write_string(&self, f: &mut fmt::Formatter<'_>, s: &[u8], colour: &str) -> fmt::Result {
+        if s.len() > self.options.max_string_len {
+            return self.paint(f, BINARY, digest_summary(s));
+        }
+        match std::str::from_utf8(s) {
+            Ok(text) => self.paint(f, colour, format_args!("{text:?}")),
+            Err(_) => self.paint(f, BINARY, format_args!("<hex {}>", hex::encode(s))),
+        }
+    }
+
+    fn paint(&self, f: &mut fmt::Formatter<'_>, colour: &str, text: impl Display) -> fmt::Result {
+        match self.options.colour {
+            true => write!(f, "{colour}{text}{RESET}"),
+            false => write!(f, "{text}"),
+        }
+    }
+
+    fn write_indent(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:width$}", "", width = depth * self.options.indent)
+    }
+
+    fn end_line(&self, f: &mut fmt::Formatter<'_>, more: bool) -> fmt::Result {
+        match more {
+            true => writeln!(f, ","),
+            false => writeln!(f),
+        }
+    }
+}
+
+impl Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_value(f, self.val, 0)
+    }
+}
+
+// Stand in for a string too long (or too binary) to print, e.g. <6840 bytes, sha1=..>
+pub(crate) fn digest_summary(s: &[u8]) -> String {
+    format!(
+        "<{} bytes, sha1={}>",
+        s.len(),
+        sha1_smol::Sha1::from(s).hexdigest()
+    )
+}
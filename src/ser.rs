@@ -0,0 +1,389 @@
+use serde::ser::{self, Serialize};
+use std::collections::BTreeMap;
+
+use crate::bencode::Bencode;
+use crate::error::SerdeError;
+
+/// Serializes `value` into canonical bencode bytes
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, SerdeError> {
+    Ok(to_value(value)?.encode_val())
+}
+
+/// Serializes `value` into a `Bencode` tree. Dictionary keys end up sorted because
+/// `Bencode::Dict` is a `BTreeMap`, whatever order the struct declares its fields in.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Bencode, SerdeError> {
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| SerdeError::Message("Top level value has no bencode representation".into()))
+}
+
+// Bencode has no null, so values like None and () serialize to Ok(None). Struct and map
+// fields holding them are left out of the dictionary entirely.
+struct Serializer;
+
+type Output = Option<Bencode>;
+
+fn required(val: Output, what: &str) -> Result<Bencode, SerdeError> {
+    val.ok_or_else(|| SerdeError::Message(format!("{what} can not be None or unit")))
+}
+
+// Bencode integers have no size limit, so anything past i64 is written out as a BigInt
+fn int<T: TryInto<i64> + Copy + std::fmt::Display>(v: T) -> Result<Output, SerdeError> {
+    match v.try_into() {
+        Ok(i) => Ok(Some(Bencode::Int(i))),
+        Err(_) => Ok(Some(Bencode::BigInt(v.to_string()))),
+    }
+}
+
+fn single_entry(variant: &str, val: Bencode) -> Output {
+    Some(Bencode::Dict(BTreeMap::from([(
+        variant.as_bytes().to_vec(),
+        val,
+    )])))
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = Output;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Output, SerdeError> {
+        int(v as i64)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Output, SerdeError> {
+        int(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Output, SerdeError> {
+        Err(SerdeError::Message(
+            "Bencode has no floating point type".into(),
+        ))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Output, SerdeError> {
+        Err(SerdeError::Message(
+            "Bencode has no floating point type".into(),
+        ))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Output, SerdeError> {
+        self.serialize_str(v.encode_utf8(&mut [0u8; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Output, SerdeError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Output, SerdeError> {
+        Ok(Some(Bencode::Message(v.to_vec())))
+    }
+
+    fn serialize_none(self) -> Result<Output, SerdeError> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Output, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Output, SerdeError> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Output, SerdeError> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Output, SerdeError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Output, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Output, SerdeError> {
+        let val = required(value.serialize(Serializer)?, variant)?;
+        Ok(single_entry(variant, val))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerdeError> {
+        Ok(SeqSerializer {
+            list: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerdeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, SerdeError> {
+        Ok(SeqSerializer {
+            list: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer, SerdeError> {
+        Ok(MapSerializer {
+            dict: BTreeMap::new(),
+            next_key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<MapSerializer, SerdeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<MapSerializer, SerdeError> {
+        Ok(MapSerializer {
+            dict: BTreeMap::new(),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+struct SeqSerializer {
+    list: Vec<Bencode>,
+    // Set for tuple variants, which are wrapped in a single entry dict keyed by the variant name
+    variant: Option<&'static str>,
+}
+
+impl SeqSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let val = required(value.serialize(Serializer)?, "List elements")?;
+        self.list.push(val);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Output, SerdeError> {
+        let list = Bencode::List(self.list);
+        Ok(match self.variant {
+            Some(variant) => single_entry(variant, list),
+            None => Some(list),
+        })
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+struct MapSerializer {
+    dict: BTreeMap<Vec<u8>, Bencode>,
+    next_key: Option<Vec<u8>>,
+    // Set for struct variants, which are wrapped in a single entry dict keyed by the variant name
+    variant: Option<&'static str>,
+}
+
+impl MapSerializer {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: Vec<u8>, value: &T) -> Result<(), SerdeError> {
+        if let Some(val) = value.serialize(Serializer)? {
+            self.dict.insert(key, val);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Output, SerdeError> {
+        let dict = Bencode::Dict(self.dict);
+        Ok(match self.variant {
+            Some(variant) => single_entry(variant, dict),
+            None => Some(dict),
+        })
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        match key.serialize(Serializer)? {
+            Some(Bencode::Message(k)) => {
+                self.next_key = Some(k);
+                Ok(())
+            }
+            _ => Err(SerdeError::Message(
+                "Dictionary keys must serialize to strings".into(),
+            )),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self.next_key.take().ok_or_else(|| {
+            SerdeError::Message("serialize_value called before serialize_key".into())
+        })?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Output;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        self.insert(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Output, SerdeError> {
+        self.finish()
+    }
+}
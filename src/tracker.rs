@@ -0,0 +1,59 @@
+use rand::seq::SliceRandom;
+
+use crate::MetaInfo;
+
+/// The trackers of a torrent in BEP 12 tiers. Each tier is shuffled once when the tiers are
+/// built, and a tracker that responds is moved to the front of its tier, so the order it
+/// leaves behind is the one to try next time. Keep one of these per torrent for as long as
+/// it is being announced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackerTiers {
+    // Never empty, and neither is any tier
+    tiers: Vec<Vec<Vec<u8>>>,
+}
+
+impl TrackerTiers {
+    /// Tiers from `announce-list`, or a single tier holding `announce` when the torrent has
+    /// no announce-list. As BEP 12 asks, `announce` is ignored when there is an announce-list.
+    /// Empty tiers are dropped, and None is returned if no tracker is left.
+    pub fn new(meta_info: &MetaInfo) -> Option<TrackerTiers> {
+        // A MetaInfo built by hand may hold empty tiers, which from_bytes never produces
+        let list = meta_info.announce_list.iter().flatten();
+        let mut tiers: Vec<_> = list.filter(|tier| !tier.is_empty()).cloned().collect();
+        if tiers.is_empty() {
+            tiers = vec![vec![meta_info.announce.clone()?]];
+        }
+        let mut rng = rand::thread_rng();
+        for tier in tiers.iter_mut() {
+            tier.shuffle(&mut rng);
+        }
+        Some(TrackerTiers { tiers })
+    }
+
+    pub fn tiers(&self) -> &[Vec<Vec<u8>>] {
+        &self.tiers
+    }
+
+    /// Calls announce with one tracker url after another, first tier first, until it
+    /// succeeds. The tracker that succeeded moves to the front of its tier. If every tracker
+    /// fails the error from the last one is returned.
+    pub fn announce<T, E>(
+        &mut self,
+        mut announce: impl FnMut(&[u8]) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut last_err = None;
+        for tier in self.tiers.iter_mut() {
+            for i in 0..tier.len() {
+                match announce(&tier[i]) {
+                    Ok(res) => {
+                        let url = tier.remove(i);
+                        tier.insert(0, url);
+                        return Ok(res);
+                    }
+                    Err(e) => last_err = Some(e),
+                }
+            }
+        }
+        Err(last_err.expect("there is always at least one tracker"))
+    }
+}
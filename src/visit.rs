@@ -0,0 +1,36 @@
+use crate::decode::{DecodeOptions, Decoder};
+use crate::error::BencodeError;
+
+/// Receives a document as a stream of events while it is decoded, instead of as a tree.
+/// Strings and keys borrow from the input. Every method does nothing by default, so a
+/// visitor only implements the events it cares about.
+///
+/// A dictionary entry is reported as `on_key` followed by the events of its value, a list
+/// element as just the events of its value.
+pub trait Visitor<'a> {
+    fn on_int(&mut self, _i: i64) {}
+    /// Integer too large for an i64, only seen with `DecodeOptions::big_ints`
+    fn on_big_int(&mut self, _digits: &'a str) {}
+    fn on_bytes(&mut self, _bytes: &'a [u8]) {}
+    fn on_list_start(&mut self) {}
+    fn on_list_end(&mut self) {}
+    fn on_dict_start(&mut self) {}
+    fn on_key(&mut self, _key: &'a [u8]) {}
+    fn on_dict_end(&mut self) {}
+}
+
+/// Feeds the single value in src to visitor. Events already delivered stay delivered if the
+/// input turns out to be invalid part way through.
+pub fn visit<'a, V: Visitor<'a>>(src: &'a [u8], visitor: &mut V) -> Result<(), BencodeError> {
+    visit_with_options(src, visitor, DecodeOptions::default())
+}
+
+pub fn visit_with_options<'a, V: Visitor<'a>>(
+    src: &'a [u8],
+    visitor: &mut V,
+    options: DecodeOptions,
+) -> Result<(), BencodeError> {
+    let mut decoder = Decoder::with_options(src, options);
+    decoder.visit_next(visitor)?;
+    decoder.finish()
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "std")]
+
+mod accessor_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::error::TypeError;
+    use bit_tor::{MetaInfo, Peer};
+    use std::collections::BTreeMap;
+
+    fn sample() -> Bencode {
+        Bencode::decode_all(b"d4:listli1ei2ee4:name5:a.txt3:rawi-7e3:tmp2:\xff\xfee")
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn borrowing_accessors() {
+        let val = sample();
+        assert_eq!(val.get("name").and_then(Bencode::as_str), Some("a.txt"));
+        assert_eq!(
+            val.get(b"tmp").and_then(Bencode::as_bytes),
+            Some(&b"\xff\xfe"[..])
+        );
+        assert_eq!(val.get("tmp").and_then(Bencode::as_str), None);
+        assert_eq!(val.get("raw").and_then(Bencode::as_int), Some(-7));
+        assert_eq!(
+            val.get("list").and_then(Bencode::as_list).map(<[_]>::len),
+            Some(2)
+        );
+        assert_eq!(val.as_dict().map(BTreeMap::len), Some(4));
+        assert_eq!(val.get("missing"), None);
+        assert_eq!(val.as_int(), None);
+        assert_eq!(Bencode::Int(3).get("name"), None);
+    }
+
+    #[test]
+    fn get_mut_edits_in_place() {
+        let mut val = sample();
+        *val.get_mut("raw").unwrap() = Bencode::Int(8);
+        val.get_mut("list")
+            .and_then(Bencode::as_list_mut)
+            .unwrap()
+            .push(Bencode::Int(3));
+        assert_eq!(
+            val.encode_val(),
+            b"d4:listli1ei2ei3ee4:name5:a.txt3:rawi8e3:tmp2:\xff\xfee"
+        );
+    }
+
+    #[test]
+    fn try_from_conversions() {
+        let val = sample();
+        assert_eq!(i32::try_from(val.get("raw").unwrap()), Ok(-7));
+        assert_eq!(
+            u32::try_from(val.get("raw").unwrap()),
+            Err(TypeError {
+                expected: "u32",
+                found: "integer -7".into()
+            })
+        );
+        assert_eq!(
+            u64::try_from(&Bencode::BigInt("18446744073709551615".into())),
+            Ok(u64::MAX)
+        );
+        assert_eq!(bool::try_from(&Bencode::Int(1)), Ok(true));
+        assert_eq!(<&str>::try_from(val.get("name").unwrap()), Ok("a.txt"));
+        let err = <&[Bencode]>::try_from(&val).unwrap_err();
+        assert_eq!(err.to_string(), "expected list, found dictionary");
+        let owned = val.get("name").unwrap().clone();
+        assert_eq!(String::try_from(owned), Ok("a.txt".to_string()));
+        assert!(String::try_from(val.get("tmp").unwrap().clone()).is_err());
+        let dict = BTreeMap::try_from(val).unwrap();
+        assert_eq!(
+            Vec::<Bencode>::try_from(dict[b"list".as_slice()].clone())
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn bad_torrents_are_errors() {
+        for src in [
+            &b"le"[..],
+            b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces3:abcee",
+            b"d8:announceli1ee4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d8:announce3:url4:infod4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d8:announce3:url4:infod5:filesli1ee4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d8:announce3:url4:infoi1ee",
+            b"d8:announce3:url4:infod6:lengthi-5e4:name1:a12:piece lengthi1e6:pieces0:ee",
+            b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi0e6:pieces0:ee",
+            b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi-1e6:pieces0:ee",
+            b"d8:announce3:url4:infod5:filesld6:lengthi-1e4:pathl1:beee4:name1:a12:piece lengthi1e6:pieces0:ee",
+        ] {
+            let err = MetaInfo::from_bytes(src).err().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        }
+        let ok = b"d8:announce3:url4:infod6:lengthi1e4:name1:a12:piece lengthi1e6:pieces0:ee";
+        assert_eq!(MetaInfo::from_bytes(ok).unwrap().info.file_length, Some(1));
+    }
+
+    #[test]
+    fn bad_tracker_responses_are_errors() {
+        for response in [&b"li1ee"[..], b"d14:failure reasoni1ee", b"d5:peersi3ee"] {
+            assert!(Peer::get_peers(response.to_vec()).is_err());
+        }
+        let err = Peer::get_peers(b"d14:failure reason4:nopee".to_vec()).unwrap_err();
+        assert!(err.to_string().contains("nope"));
+    }
+}
@@ -0,0 +1,79 @@
+mod bencode_ref_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::bencode_ref::BencodeRef;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn message_borrows_from_input() {
+        let s = "12:Hello World!".as_bytes();
+        match BencodeRef::decode(s).unwrap() {
+            BencodeRef::Message(m) => {
+                assert_eq!(m, &s[3..]);
+                assert!(std::ptr::eq(m.as_ptr(), s[3..].as_ptr()));
+            }
+            x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn decode_zero() {
+        assert_eq!(BencodeRef::decode(b"i0e").unwrap(), BencodeRef::Int(0));
+    }
+
+    #[test]
+    fn nested_dict_borrowed() {
+        let s = "d4:infod6:lengthi42e6:pieces3:abce3:keyl1:xi-1eee".as_bytes();
+        let exp = BencodeRef::Dict(BTreeMap::from([
+            (
+                "info".as_bytes(),
+                BencodeRef::Dict(BTreeMap::from([
+                    ("length".as_bytes(), BencodeRef::Int(42)),
+                    ("pieces".as_bytes(), BencodeRef::Message(b"abc")),
+                ])),
+            ),
+            (
+                "key".as_bytes(),
+                BencodeRef::List(vec![BencodeRef::Message(b"x"), BencodeRef::Int(-1)]),
+            ),
+        ]));
+        assert_eq!(BencodeRef::decode(s).unwrap(), exp);
+    }
+
+    #[test]
+    fn into_owned_matches_owned_decode() {
+        let s = "d3:cow3:moo4:spaml1:a1:bi7eee".as_bytes();
+        let borrowed = BencodeRef::decode(s).unwrap();
+        assert_eq!(borrowed.into_owned(), Bencode::decode_all(s).unwrap()[0]);
+    }
+
+    #[test]
+    fn decode_all_multiple_vals() {
+        let s = "i33e4:spamle".as_bytes();
+        let vals = BencodeRef::decode_all(s).unwrap();
+        assert_eq!(
+            vals,
+            vec![
+                BencodeRef::Int(33),
+                BencodeRef::Message(b"spam"),
+                BencodeRef::List(Vec::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn message_length_past_end() {
+        assert!(BencodeRef::decode(b"99999999999:abc").is_err());
+    }
+
+    #[test]
+    fn decode_dispatch_advances_iterator() {
+        let s = "4:spami3e".as_bytes().to_vec();
+        let mut it = s.iter().peekable();
+        assert_eq!(
+            Bencode::decode_dispatch(&mut it).unwrap(),
+            Bencode::Message(b"spam".to_vec())
+        );
+        assert_eq!(Bencode::decode_dispatch(&mut it).unwrap(), Bencode::Int(3));
+        assert!(it.peek().is_none());
+    }
+}
@@ -0,0 +1,75 @@
+mod canonical_tests {
+    use bit_tor::bencode::{canonicalize, is_canonical, Bencode, CANONICAL_MAX_DEPTH};
+    use bit_tor::decode::{DecodeOptions, Decoder};
+    use bit_tor::error::{BencodeErrorKind, KeyPath};
+
+    #[test]
+    fn canonical_documents() {
+        for src in [
+            &b"i0e"[..],
+            b"i-12e",
+            b"0:",
+            b"d1:ai1e1:bli2eee",
+            b"i123456789012345678901234567890e",
+        ] {
+            assert!(is_canonical(src), "{src:?}");
+            assert_eq!(canonicalize(src).unwrap(), src);
+        }
+    }
+
+    #[test]
+    fn non_canonical_documents() {
+        for (src, canonical) in [
+            (&b"i03e"[..], &b"i3e"[..]),
+            (b"i-0e", b"i0e"),
+            (b"03:abc", b"3:abc"),
+            (b"d1:bi1e1:ai2ee", b"d1:ai2e1:bi1ee"),
+            (b"d1:ai1e1:ai2ee", b"d1:ai1ee"),
+            (
+                b"i-000123456789012345678901234567890e",
+                b"i-123456789012345678901234567890e",
+            ),
+        ] {
+            assert!(!is_canonical(src), "{src:?}");
+            assert_eq!(canonicalize(src).unwrap(), canonical);
+            assert!(is_canonical(canonical));
+        }
+    }
+
+    #[test]
+    fn trailing_data_is_not_canonical() {
+        assert!(!is_canonical(b"i1ei2e"));
+        assert!(canonicalize(b"i1ei2e").is_err());
+        assert!(canonicalize(b"d1:a").is_err());
+    }
+
+    #[test]
+    fn info_dict_survives_round_trip() {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let options = DecodeOptions {
+            record_spans: true,
+            ..Default::default()
+        };
+        let mut decoder = Decoder::with_options(&src, options);
+        let root = decoder.decode_next().unwrap().into_owned();
+        let info_span = decoder.spans().get(&KeyPath::root().key(b"info")).unwrap();
+        assert!(is_canonical(&src[info_span.clone()]));
+        let info: &Bencode = root.get("info").unwrap();
+        assert_eq!(info.encode_val(), &src[info_span]);
+    }
+
+    #[test]
+    fn depth_limit_is_explicit() {
+        // Deeper than the default decode limit, well within the canonical one
+        let nested = |depth: usize| [vec![b'l'; depth], vec![b'e'; depth]].concat();
+        let src = nested(300);
+        assert!(is_canonical(&src));
+        assert_eq!(canonicalize(&src).unwrap(), src);
+        let src = nested(CANONICAL_MAX_DEPTH);
+        assert_eq!(canonicalize(&src).unwrap(), src);
+        let src = nested(CANONICAL_MAX_DEPTH + 1);
+        assert!(!is_canonical(&src));
+        let err = canonicalize(&src).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TooDeep);
+    }
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "std")]
+
+mod create_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::create::TorrentBuilder;
+    use bit_tor::MetaInfo;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // A fresh directory under the system temp dir, unique to the test
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("bit_tor_create_{}_{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sha1(data: &[u8]) -> [u8; 20] {
+        sha1_smol::Sha1::from(data).digest().bytes()
+    }
+
+    #[test]
+    fn single_file() {
+        let dir = temp_dir("single_file");
+        let path = dir.join("a.bin");
+        let data: Vec<u8> = (0..40000u32).map(|i| i as u8).collect();
+        fs::write(&path, &data).unwrap();
+        let torrent = TorrentBuilder::new(&path)
+            .piece_length(16384)
+            .tracker("http://tracker.example/announce")
+            .creation_date(Some(1700000000))
+            .to_bytes()
+            .unwrap();
+        let meta = MetaInfo::from_bytes(&torrent).unwrap();
+        assert!(meta.info.single_file);
+        assert_eq!(meta.info.name, b"a.bin");
+        assert_eq!(meta.info.file_length, Some(40000));
+        assert_eq!(
+            meta.announce,
+            Some(b"http://tracker.example/announce".to_vec())
+        );
+        assert_eq!(meta.announce_list, None);
+        assert_eq!(meta.creation_date, Some(1700000000));
+        let pieces = meta.info.pieces.unwrap();
+        assert_eq!(pieces.len(), 3);
+        assert_eq!(pieces[0], sha1(&data[..16384]));
+        assert_eq!(pieces[2], sha1(&data[32768..]));
+    }
+
+    #[test]
+    fn pieces_span_files() {
+        let dir = temp_dir("pieces_span_files");
+        let root = dir.join("dist");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("b.txt"), vec![b'b'; 10000]).unwrap();
+        fs::write(root.join("a.txt"), vec![b'a'; 10000]).unwrap();
+        fs::write(root.join("sub").join("c.txt"), vec![b'c'; 20000]).unwrap();
+        let meta = MetaInfo::from_bytes(
+            &TorrentBuilder::new(&root)
+                .piece_length(16384)
+                .to_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        let files = meta.info.files.unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            [
+                vec![b"a.txt".to_vec()],
+                vec![b"b.txt".to_vec()],
+                vec![b"sub".to_vec(), b"c.txt".to_vec()]
+            ]
+        );
+        // Files are hashed as one stream in that order
+        let mut all = vec![b'a'; 10000];
+        all.extend(vec![b'b'; 10000]);
+        all.extend(vec![b'c'; 20000]);
+        let expected: Vec<_> = all.chunks(16384).map(sha1).collect();
+        assert_eq!(meta.info.pieces.unwrap(), expected);
+    }
+
+    #[test]
+    fn trackers_and_flags() {
+        let dir = temp_dir("trackers_and_flags");
+        let path = dir.join("a");
+        fs::write(&path, b"hello").unwrap();
+        let builder = TorrentBuilder::new(&path)
+            .tier(vec!["http://a".to_string(), "http://b".to_string()])
+            .tracker("http://c")
+            .web_seed("http://seed.example/a")
+            .comment("test")
+            .private(true)
+            .creation_date(None);
+        let val = builder.build().unwrap();
+        assert_eq!(
+            val.get("info").unwrap().get("private"),
+            Some(&Bencode::Int(1))
+        );
+        assert_eq!(val.get("creation date"), None);
+        let meta = MetaInfo::from_bytes(&val.encode_val()).unwrap();
+        assert_eq!(meta.announce, Some(b"http://a".to_vec()));
+        assert_eq!(
+            meta.announce_list,
+            Some(vec![
+                vec![b"http://a".to_vec(), b"http://b".to_vec()],
+                vec![b"http://c".to_vec()]
+            ])
+        );
+        assert_eq!(meta.comment, Some(b"test".to_vec()));
+        // Without a date the same files always give the same torrent
+        assert_eq!(builder.to_bytes().unwrap(), val.encode_val());
+    }
+
+    #[test]
+    fn piece_length() {
+        let dir = temp_dir("piece_length");
+        let path = dir.join("a");
+        fs::write(&path, vec![0; 100]).unwrap();
+        let val = TorrentBuilder::new(&path).build().unwrap();
+        let info = val.get("info").unwrap();
+        assert_eq!(info.get("piece length"), Some(&Bencode::Int(16384)));
+        assert!(TorrentBuilder::new(&path)
+            .piece_length(20000)
+            .build()
+            .is_err());
+        assert!(TorrentBuilder::new(&path)
+            .piece_length(8192)
+            .build()
+            .is_err());
+        assert!(TorrentBuilder::new(dir.join("missing")).build().is_err());
+        let empty = dir.join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        assert!(TorrentBuilder::new(&empty).build().is_err());
+    }
+}
@@ -1,6 +1,6 @@
 mod decode_tests {
     use bit_tor::bencode::Bencode;
-    use std::{collections::BTreeMap, error::Error};
+    use std::collections::BTreeMap;
 
     #[test]
     fn decode_pos_int() {
@@ -31,6 +31,7 @@ mod decode_tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn neg_int_decode() {
         let num = -32;
         let s = format!("i{num}e").as_bytes().to_vec();
@@ -73,6 +74,17 @@ mod decode_tests {
     }
 
     #[test]
+    fn malformed_int_bytes() {
+        for src in [
+            "ie", "i-e", "i+1e", "i1-2e", "i--1e", "i 1e", "i1 e", "i0x1e",
+        ] {
+            let mut s = src.as_bytes().iter().peekable();
+            assert!(Bencode::decode_dispatch(&mut s).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_message_decode() {
         let s = String::from("12:Hello World!").as_bytes().to_vec();
         let res = Bencode::decode_dispatch(&mut s.iter().peekable()).unwrap();
@@ -93,6 +105,7 @@ mod decode_tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_message_with_bencoded_vals() {
         let s = "35:abcd12:hello_world!li22ed3:eari45ee".as_bytes().to_vec();
         match Bencode::decode_dispatch(&mut s.iter().peekable()).unwrap() {
@@ -104,6 +117,7 @@ mod decode_tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_decode_multiple_vals_ints() {
         let s = "i33ei-1e".as_bytes().to_vec();
         let v = Bencode::decode_all(&s).unwrap();
@@ -187,6 +201,7 @@ mod decode_tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_dict_decode_pos() {
         let mut s = "d3:cow3:moo4:spam4:eggse".as_bytes().iter().peekable();
         let vals = BTreeMap::from([
@@ -0,0 +1,70 @@
+#![cfg(feature = "std")]
+
+mod diff_tests {
+    use bit_tor::bencode::{diff, Bencode};
+    use bit_tor::diff::{summarize, Change};
+    use bit_tor::error::KeyPath;
+
+    fn decode(src: &[u8]) -> Bencode {
+        Bencode::decode_all(src).unwrap().remove(0)
+    }
+
+    #[test]
+    fn identical_documents() {
+        let a = decode(b"d4:infod4:name1:aee");
+        assert!(diff(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn added_removed_changed() {
+        let old = decode(b"d7:comment2:hi4:infod6:lengthi1e4:name1:aee");
+        let new = decode(b"d10:created by2:me4:infod6:lengthi2e4:name1:aee");
+        let d = diff(&old, &new);
+        assert_eq!(d.len(), 3);
+        assert_eq!(d[0].path, KeyPath::root().key(b"comment"));
+        assert!(matches!(d[0].change, Change::Removed(_)));
+        assert_eq!(d[1].path, KeyPath::root().key(b"created by"));
+        assert!(matches!(d[1].change, Change::Added(_)));
+        assert_eq!(
+            d[2].change,
+            Change::Changed {
+                old: &Bencode::Int(1),
+                new: &Bencode::Int(2)
+            }
+        );
+        assert_eq!(d[2].to_string(), "~ info.length: 1 -> 2");
+        assert!(!d[0].in_info() && !d[1].in_info() && d[2].in_info());
+    }
+
+    #[test]
+    fn lists_compare_by_index() {
+        let old = decode(b"d8:url-listl1:a1:bee");
+        let new = decode(b"d8:url-listl1:a1:c1:dee");
+        let lines: Vec<String> = diff(&old, &new).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec!["~ url-list[1]: \"b\" -> \"c\"", "+ url-list[2]: \"d\""]
+        );
+    }
+
+    #[test]
+    fn type_change_and_root() {
+        let (old, new) = (Bencode::Int(1), Bencode::List(vec![]));
+        let d = diff(&old, &new);
+        assert_eq!(d.len(), 1);
+        assert_eq!(d[0].to_string(), "~ <root>: 1 -> <list of 0 items>");
+    }
+
+    #[test]
+    fn binary_is_summarized() {
+        let pieces = Bencode::Message(vec![0xff; 40]);
+        assert_eq!(
+            summarize(&pieces),
+            format!(
+                "<40 bytes, sha1={}>",
+                sha1_smol::Sha1::from([0xff; 40]).hexdigest()
+            )
+        );
+        assert_eq!(summarize(&Bencode::Message(b"ok".to_vec())), "\"ok\"");
+    }
+}
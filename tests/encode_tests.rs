@@ -1,5 +1,7 @@
 mod encode_tests {
     use bit_tor::bencode::Bencode;
+    use bit_tor::encode::Encoder;
+    use bit_tor::error::EncodeError;
     use std::collections::BTreeMap;
 
     #[test]
@@ -139,4 +141,67 @@ mod encode_tests {
                 .to_vec();
         assert_eq!(te.encode_val(), exp)
     }
+
+    #[test]
+    fn encoded_len_matches_encoding() {
+        let te = Bencode::Dict(BTreeMap::from([
+            ("a".as_bytes().to_vec(), Bencode::Int(-1234567)),
+            ("b".as_bytes().to_vec(), Bencode::Int(0)),
+            (
+                "long".as_bytes().to_vec(),
+                Bencode::List(vec![
+                    Bencode::Message(vec![7u8; 12345]),
+                    Bencode::Int(i64::MIN),
+                    Bencode::List(Vec::new()),
+                ]),
+            ),
+        ]));
+        assert_eq!(te.encoded_len(), te.encode_val().len())
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn encode_to_writer() {
+        let te = Bencode::List(vec![
+            Bencode::Message("spam".as_bytes().to_vec()),
+            Bencode::Int(42),
+        ]);
+        let mut out = std::io::Cursor::new(Vec::new());
+        te.encode_to(&mut out).unwrap();
+        assert_eq!(out.into_inner(), "l4:spami42ee".as_bytes())
+    }
+
+    #[test]
+    fn encoder_multiple_values() {
+        let mut enc = Encoder::new(Vec::new());
+        enc.encode(&Bencode::Int(1)).unwrap();
+        enc.write_message("ab".as_bytes()).unwrap();
+        assert!(enc.encode(&Bencode::Stop).is_err());
+        assert_eq!(enc.into_inner(), "i1e2:ab".as_bytes())
+    }
+
+    #[test]
+    fn stop_is_an_encode_error() {
+        let mut out = Vec::new();
+        let res = Bencode::List(vec![Bencode::Stop]).encode_to(&mut out);
+        assert!(matches!(res, Err(EncodeError::Stop)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Stop val passed to encode_val.")]
+    fn nested_stop_panics() {
+        Bencode::List(vec![Bencode::Int(1), Bencode::Stop]).encode_val();
+    }
+
+    #[test]
+    fn encoder_int_digits() {
+        let mut enc = Encoder::new(Vec::new());
+        for i in [i64::MIN, -10, -1, 0, 9, 10, i64::MAX] {
+            enc.write_int(i).unwrap();
+        }
+        assert_eq!(
+            enc.into_inner(),
+            "i-9223372036854775808ei-10ei-1ei0ei9ei10ei9223372036854775807e".as_bytes()
+        )
+    }
 }
@@ -0,0 +1,71 @@
+mod error_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::bencode_ref::BencodeRef;
+    use bit_tor::error::{BencodeErrorKind, KeyPath, PathSegment};
+
+    #[test]
+    fn nested_error_path_and_offset() {
+        let s = "d4:infod5:filesld6:lengthi1e4:pathl1:aeed6:lengthi03eeeee".as_bytes();
+        let err = BencodeRef::decode(s).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::InvalidInt(b"03".to_vec()));
+        assert_eq!(err.offset, s.iter().position(|c| *c == b'0').unwrap() - 1);
+        assert_eq!(
+            err.path,
+            KeyPath(vec![
+                PathSegment::Key(b"info".to_vec()),
+                PathSegment::Key(b"files".to_vec()),
+                PathSegment::Index(1),
+                PathSegment::Key(b"length".to_vec()),
+            ])
+        );
+        assert_eq!(err.path.to_string(), "info.files[1].length");
+    }
+
+    #[test]
+    fn unterminated_int() {
+        let err = BencodeRef::decode(b"li1ei-45").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnterminatedInt);
+        assert_eq!(err.offset, 4);
+        assert_eq!(err.path.to_string(), "[1]");
+    }
+
+    #[test]
+    fn unsorted_and_duplicate_keys() {
+        let err = BencodeRef::decode(b"d1:bi1e1:ai2ee").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnsortedKeys(b"a".to_vec()));
+        assert_eq!(err.offset, 7);
+        let err = BencodeRef::decode(b"d1:ai1e1:ai2ee").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::DuplicateKey(b"a".to_vec()));
+    }
+
+    #[test]
+    fn short_message_is_eof() {
+        let err = Bencode::decode_all(b"12:hello ").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn unexpected_byte_and_missing_value() {
+        let err = BencodeRef::decode(b"lxe").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnexpectedByte(b'x'));
+        assert_eq!(err.offset, 1);
+        let err = BencodeRef::decode(b"d3:keye").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::MissingValue(b"key".to_vec()));
+        let err = BencodeRef::decode(b"di1ei2ee").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::NonStringKey);
+    }
+
+    #[test]
+    fn trailing_data() {
+        let err = BencodeRef::decode(b"i1ejunk").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TrailingData);
+        assert_eq!(err.offset, 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn converts_to_io_error() {
+        let err: std::io::Error = BencodeRef::decode(b"i1").unwrap_err().into();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
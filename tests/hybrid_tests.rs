@@ -0,0 +1,129 @@
+#![cfg(feature = "std")]
+
+mod hybrid_tests {
+    use bit_tor::bencode;
+    use bit_tor::bencode::Bencode;
+    use bit_tor::{announce_url, MetaInfo};
+    use sha2::{Digest, Sha256};
+
+    // Two files with 16 KiB pieces. "a" takes up two pieces, so on the v1 side a padding file
+    // fills the rest of its second piece and "b" starts the third.
+    fn hybrid() -> Bencode {
+        bencode!({
+            "announce" => "http://tracker.example/announce",
+            "info" => {
+                "file tree" => {
+                    "a" => { "" => { "length" => 20000, "pieces root" => &[1; 32] } },
+                    "b" => { "" => { "length" => 100, "pieces root" => &[2; 32] } },
+                },
+                "files" => [
+                    { "length" => 20000, "path" => ["a"] },
+                    { "attr" => "p", "length" => 12768, "path" => [".pad", "12768"] },
+                    { "length" => 100, "path" => ["b"] },
+                ],
+                "meta version" => 2,
+                "name" => "dir",
+                "piece length" => 16384,
+                "pieces" => vec![0u8; 60].as_slice(),
+            },
+            "piece layers" => { vec![1u8; 32] => vec![0u8; 64].as_slice() },
+        })
+    }
+
+    fn parse(val: &Bencode) -> std::io::Result<MetaInfo> {
+        MetaInfo::from_bytes(&val.encode_val())
+    }
+
+    #[test]
+    fn both_hashes() {
+        let val = hybrid();
+        let meta = parse(&val).unwrap();
+        let info = val.get("info").unwrap().encode_val();
+        let v1 = sha1_smol::Sha1::from(&info).digest().bytes();
+        let v2: [u8; 32] = Sha256::digest(&info).into();
+        assert!(meta.is_hybrid());
+        assert_eq!(meta.info_hash, v1);
+        assert_eq!(meta.info_hash_v1(), Some(v1));
+        assert_eq!(meta.info_hash_v2, Some(v2));
+        assert_eq!(
+            meta.swarm_hashes(),
+            [v1, meta.truncated_info_hash_v2().unwrap()]
+        );
+    }
+
+    #[test]
+    fn padding_files() {
+        let meta = parse(&hybrid()).unwrap();
+        let files = meta.info.files.unwrap();
+        let padding: Vec<bool> = files.iter().map(|f| f.is_padding()).collect();
+        assert_eq!(padding, [false, true, false]);
+        assert_eq!(files[1].attr, b"p");
+        assert_eq!(meta.info.file_tree.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn views_must_agree() {
+        let mut val = hybrid();
+        let tree = val.get_mut("info").unwrap().get_mut("file tree").unwrap();
+        let b = tree.get_mut("b").unwrap().get_mut("").unwrap();
+        *b.get_mut("length").unwrap() = Bencode::Int(101);
+        assert!(parse(&val).is_err());
+        // Without the padding file "b" no longer starts on a piece boundary
+        let mut val = hybrid();
+        let files = val.get_mut("info").unwrap().get_mut("files").unwrap();
+        files.as_list_mut().unwrap().remove(1);
+        assert!(parse(&val).is_err());
+        let mut val = hybrid();
+        let info = val.get_mut("info").unwrap();
+        *info.get_mut("pieces").unwrap() = Bencode::Message(vec![0; 40]);
+        assert!(parse(&val).is_err());
+    }
+
+    #[test]
+    fn huge_lengths_are_errors() {
+        // "a" ends on a piece boundary, so the sum of lengths is reached and overflows
+        let a = i64::MAX - i64::MAX % 16384;
+        let mut val = hybrid();
+        let info = val.get_mut("info").unwrap();
+        let tree = info.get_mut("file tree").unwrap();
+        let file = tree.get_mut("a").unwrap().get_mut("").unwrap();
+        *file.get_mut("length").unwrap() = Bencode::Int(a);
+        let file = tree.get_mut("b").unwrap().get_mut("").unwrap();
+        *file.get_mut("length").unwrap() = Bencode::Int(16384);
+        let files = info.get_mut("files").unwrap().as_list_mut().unwrap();
+        files.remove(1);
+        *files[0].get_mut("length").unwrap() = Bencode::Int(a);
+        *files[1].get_mut("length").unwrap() = Bencode::Int(16384);
+        let err = parse(&val).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn announce_with_either_hash() {
+        let meta = parse(&hybrid()).unwrap();
+        let url = b"http://tracker.example/announce";
+        for hash in meta.swarm_hashes() {
+            let escaped: String = hash
+                .iter()
+                .map(
+                    |&b| match b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                        true => (b as char).to_string(),
+                        false => format!("%{b:02X}"),
+                    },
+                )
+                .collect();
+            let announce = announce_url(url, &hash, "-AI0001-123456789012");
+            assert!(announce.starts_with("http://tracker.example/announce?"));
+            assert!(announce.contains(&format!("info_hash={escaped}&")));
+        }
+        // Plain v1 and v2 torrents are in one swarm
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        assert!(!meta.is_hybrid());
+        assert_eq!(meta.swarm_hashes(), [meta.info_hash]);
+        let src = std::fs::read("sample_torrent/bittorrent-v2-test.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        assert_eq!(meta.info_hash_v1(), None);
+        assert_eq!(meta.swarm_hashes(), [meta.info_hash]);
+    }
+}
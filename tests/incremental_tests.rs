@@ -0,0 +1,88 @@
+mod incremental_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::decode::{DecodeOptions, Decoder};
+    use bit_tor::error::BencodeErrorKind;
+    use bit_tor::incremental::{IncrementalDecoder, Progress};
+
+    #[test]
+    fn byte_at_a_time() {
+        let s = "d4:infod6:lengthi-42e6:pieces5:abcdee5:peersl2:abi7eee".as_bytes();
+        let mut decoder = IncrementalDecoder::new();
+        for b in &s[..s.len() - 1] {
+            assert_eq!(decoder.feed(&[*b]).unwrap(), Progress::NeedMore);
+        }
+        let exp = Bencode::decode_all(s).unwrap().remove(0);
+        assert_eq!(
+            decoder.feed(&s[s.len() - 1..]).unwrap(),
+            Progress::Complete(exp)
+        );
+        assert!(decoder.buffered().is_empty());
+    }
+
+    #[test]
+    fn split_inside_string_and_int() {
+        let mut decoder = IncrementalDecoder::new();
+        assert_eq!(decoder.feed(b"l1").unwrap(), Progress::NeedMore);
+        assert_eq!(decoder.feed(b"1:hello").unwrap(), Progress::NeedMore);
+        assert_eq!(decoder.feed(b" worldi12").unwrap(), Progress::NeedMore);
+        assert_eq!(decoder.feed(b"3").unwrap(), Progress::NeedMore);
+        assert_eq!(
+            decoder.feed(b"ee").unwrap(),
+            Progress::Complete(Bencode::List(vec![
+                Bencode::Message(b"hello world".to_vec()),
+                Bencode::Int(123),
+            ]))
+        );
+    }
+
+    #[test]
+    fn consecutive_values() {
+        let mut decoder = IncrementalDecoder::new();
+        assert_eq!(
+            decoder.feed(b"i1e3:ab").unwrap(),
+            Progress::Complete(Bencode::Int(1))
+        );
+        assert_eq!(decoder.buffered(), b"3:ab");
+        assert_eq!(
+            decoder.feed(b"c").unwrap(),
+            Progress::Complete(Bencode::Message(b"abc".to_vec()))
+        );
+    }
+
+    #[test]
+    fn errors_carry_stream_offset_and_path() {
+        let mut decoder = IncrementalDecoder::new();
+        assert_eq!(decoder.feed(b"d1:ali").unwrap(), Progress::NeedMore);
+        let err = decoder.feed(b"1ei0x").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnterminatedInt);
+        assert_eq!(err.offset, 8);
+        assert_eq!(err.path.to_string(), "a[1]");
+    }
+
+    #[test]
+    fn unsorted_keys_rejected() {
+        let mut decoder = IncrementalDecoder::new();
+        let err = decoder.feed(b"d1:bi1e1:ai2ee").unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::UnsortedKeys(b"a".to_vec()));
+        assert_eq!(err.offset, 7);
+    }
+
+    #[test]
+    fn lenient_warnings_match_decoder() {
+        // Only "a" is out of order, "a2" comes after the key just before it
+        let src = b"d1:bi1e1:ai2e2:a2i3ee";
+        let options = DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let mut decoder = Decoder::with_options(src, options);
+        decoder.decode_next().unwrap();
+        let mut incremental = IncrementalDecoder::with_options(options);
+        incremental.feed(src).unwrap();
+        assert_eq!(incremental.warnings(), decoder.warnings());
+        assert_eq!(
+            incremental.warnings()[0].kind,
+            BencodeErrorKind::UnsortedKeys(b"a".to_vec())
+        );
+    }
+}
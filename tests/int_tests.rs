@@ -0,0 +1,96 @@
+mod int_tests {
+    use bit_tor::bencode::Bencode;
+    #[cfg(feature = "std")]
+    use bit_tor::bencode::{from_bytes, to_bytes};
+    use bit_tor::bencode_ref::BencodeRef;
+    use bit_tor::decode::DecodeOptions;
+    use bit_tor::encode::{encoded_len, Encoder};
+    use bit_tor::error::{BencodeErrorKind, EncodeError};
+    use bit_tor::incremental::{IncrementalDecoder, Progress};
+
+    fn big_ints() -> DecodeOptions {
+        DecodeOptions {
+            big_ints: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn i64_bounds() {
+        let max = Bencode::decode_all(b"i9223372036854775807e").unwrap();
+        assert_eq!(max, vec![Bencode::Int(i64::MAX)]);
+        let min = Bencode::decode_all(b"i-9223372036854775808e").unwrap();
+        assert_eq!(min, vec![Bencode::Int(i64::MIN)]);
+        assert_eq!(min[0].encode_val(), b"i-9223372036854775808e");
+    }
+
+    #[test]
+    fn overflow_is_an_error_by_default() {
+        let err = Bencode::decode_all(b"i9223372036854775808e").unwrap_err();
+        assert_eq!(
+            err.kind,
+            BencodeErrorKind::IntOverflow(b"9223372036854775808".to_vec())
+        );
+    }
+
+    #[test]
+    fn big_ints_round_trip() {
+        for src in [
+            &b"i9223372036854775808e"[..],
+            b"i-9223372036854775809e",
+            b"i123456789012345678901234567890123456789012345678901234567890e",
+        ] {
+            let val = Bencode::decode_with_options(src, big_ints()).unwrap();
+            let digits = std::str::from_utf8(&src[1..src.len() - 1]).unwrap();
+            assert_eq!(val, Bencode::BigInt(digits.to_string()));
+            assert_eq!(val.encode_val(), src);
+            assert_eq!(encoded_len(&val), src.len());
+        }
+        // Values that fit stay plain integers
+        let small = BencodeRef::decode_with_options(b"i42e", big_ints()).unwrap();
+        assert_eq!(small, BencodeRef::Int(42));
+    }
+
+    #[test]
+    fn big_ints_are_still_validated() {
+        let err = Bencode::decode_with_options(b"i099999999999999999999e", big_ints()).unwrap_err();
+        assert!(matches!(err.kind, BencodeErrorKind::InvalidInt(_)));
+        let mut dec = IncrementalDecoder::with_options(big_ints());
+        assert_eq!(dec.feed(b"li99999999999").unwrap(), Progress::NeedMore);
+        assert_eq!(
+            dec.feed(b"999999999999ee").unwrap(),
+            Progress::Complete(Bencode::List(vec![Bencode::BigInt(
+                "99999999999999999999999".into()
+            )]))
+        );
+    }
+
+    #[test]
+    fn invalid_big_ints_are_not_encoded() {
+        for digits in ["", "-", "-0", "007", "12a", "1e5", "12e3:ab"] {
+            let val = Bencode::List(vec![Bencode::BigInt(digits.to_string())]);
+            let res = Encoder::new(Vec::new()).encode(&val);
+            assert!(
+                matches!(&res, Err(EncodeError::InvalidBigInt(s)) if s == digits),
+                "{digits}"
+            );
+        }
+        let zero = Bencode::BigInt("0".to_string());
+        assert_eq!(zero.encode_val(), b"i0e");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serde_wide_integers() {
+        let len = u64::MAX;
+        let encoded = to_bytes(&len).unwrap();
+        assert_eq!(encoded, b"i18446744073709551615e");
+        assert_eq!(from_bytes::<u64>(&encoded).unwrap(), len);
+        let encoded = to_bytes(&i128::MIN).unwrap();
+        assert_eq!(from_bytes::<i128>(&encoded).unwrap(), i128::MIN);
+        assert!(from_bytes::<i64>(b"i9223372036854775808e").is_err());
+        let huge = "1".repeat(50);
+        let encoded = format!("i{huge}e");
+        assert_eq!(from_bytes::<String>(encoded.as_bytes()).unwrap(), huge);
+    }
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "std")]
+
+mod json_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::json::{from_json, to_json};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn round_trip(val: &Bencode) -> Bencode {
+        let text = serde_json::to_string(&to_json(val).unwrap()).unwrap();
+        from_json(&serde_json::from_str(&text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn torrent_round_trips() {
+        for path in [
+            "sample_torrent/big-buck-bunny.torrent",
+            "sample_torrent/bittorrent-v2-test.torrent",
+            "sample_torrent/debian-edu-12.1.0-amd64-netinst.iso.torrent",
+        ] {
+            let src = std::fs::read(path).unwrap();
+            let val = Bencode::decode_all(&src).unwrap().remove(0);
+            assert_eq!(round_trip(&val).encode_val(), src);
+        }
+    }
+
+    #[test]
+    fn strings_and_binary() {
+        let val = Bencode::Dict(BTreeMap::from([
+            (b"name".to_vec(), Bencode::Message(b"a.txt".to_vec())),
+            (
+                b"peers".to_vec(),
+                Bencode::Message(vec![0x7f, 0, 0, 1, 0x1a, 0xe1]),
+            ),
+        ]));
+        assert_eq!(
+            to_json(&val).unwrap(),
+            json!({"name": "a.txt", "peers": {"$hex": "7f0000011ae1"}})
+        );
+        assert_eq!(round_trip(&val), val);
+    }
+
+    #[test]
+    fn keys_that_look_like_tags() {
+        let val = Bencode::Dict(BTreeMap::from([
+            (b"$hex".to_vec(), Bencode::Message(b"00".to_vec())),
+            (vec![0xff, 0x00], Bencode::Int(1)),
+        ]));
+        assert_eq!(
+            to_json(&val).unwrap(),
+            json!({"$$hex": "00", "$hex:ff00": 1})
+        );
+        assert_eq!(round_trip(&val), val);
+    }
+
+    #[test]
+    fn integers() {
+        let big = Bencode::BigInt("123456789012345678901234567890".into());
+        assert_eq!(
+            to_json(&big).unwrap(),
+            json!({"$int": "123456789012345678901234567890"})
+        );
+        assert_eq!(round_trip(&big), big);
+        assert_eq!(
+            from_json(&json!(u64::MAX)).unwrap(),
+            Bencode::BigInt(u64::MAX.to_string())
+        );
+        assert_eq!(from_json(&json!({"$int": "-5"})).unwrap(), Bencode::Int(-5));
+        assert_eq!(from_json(&json!(true)).unwrap(), Bencode::Int(1));
+    }
+
+    #[test]
+    fn rejected_json() {
+        assert!(from_json(&json!(1.5)).is_err());
+        assert!(from_json(&json!(null)).is_err());
+        assert!(from_json(&json!({"$hex": "abc"})).is_err());
+        assert!(from_json(&json!({"$int": "007"})).is_err());
+        assert!(to_json(&Bencode::Stop).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+mod lenient_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::bencode_ref::BencodeRef;
+    use bit_tor::decode::DecodeOptions;
+    use bit_tor::error::BencodeErrorKind;
+    use bit_tor::incremental::{IncrementalDecoder, Progress};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn unsorted_keys_are_normalised() {
+        let s = "d1:bi1e1:ai2e1:ci3ee".as_bytes();
+        assert!(Bencode::decode_all(s).is_err());
+        let (val, warnings) = Bencode::decode_lenient(s).unwrap();
+        assert_eq!(val.encode_val(), "d1:ai2e1:bi1e1:ci3ee".as_bytes());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].kind,
+            BencodeErrorKind::UnsortedKeys(b"a".to_vec())
+        );
+        assert_eq!(warnings[0].offset, 7);
+    }
+
+    #[test]
+    fn first_duplicate_wins() {
+        let s = "d4:infod1:xi1e1:xi2eee".as_bytes();
+        let (val, warnings) = Bencode::decode_lenient(s).unwrap();
+        let inner = BTreeMap::from([(b"x".to_vec(), Bencode::Int(1))]);
+        assert_eq!(
+            val,
+            Bencode::Dict(BTreeMap::from([(b"info".to_vec(), Bencode::Dict(inner))]))
+        );
+        assert_eq!(
+            warnings[0].kind,
+            BencodeErrorKind::DuplicateKey(b"x".to_vec())
+        );
+        assert_eq!(warnings[0].path.to_string(), "info");
+    }
+
+    #[test]
+    fn padded_numbers() {
+        let (val, warnings) = BencodeRef::decode_lenient(b"li03ei-0ei-007e03:abce").unwrap();
+        assert_eq!(
+            val,
+            BencodeRef::List(vec![
+                BencodeRef::Int(3),
+                BencodeRef::Int(0),
+                BencodeRef::Int(-7),
+                BencodeRef::Message(b"abc"),
+            ])
+        );
+        let kinds: Vec<_> = warnings.into_iter().map(|w| w.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BencodeErrorKind::InvalidInt(b"03".to_vec()),
+                BencodeErrorKind::InvalidInt(b"-0".to_vec()),
+                BencodeErrorKind::InvalidInt(b"-007".to_vec()),
+                BencodeErrorKind::BadLength(b"03".to_vec()),
+            ]
+        );
+        assert!(BencodeRef::decode(b"03:abc").is_err());
+    }
+
+    #[test]
+    fn lenient_still_rejects_garbage() {
+        assert!(Bencode::decode_lenient(b"i1x2e").is_err());
+        assert!(Bencode::decode_lenient(b"ie").is_err());
+        assert!(Bencode::decode_lenient(b"d1:ae").is_err());
+    }
+
+    #[test]
+    fn incremental_lenient() {
+        let mut decoder = IncrementalDecoder::with_options(DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        });
+        assert_eq!(decoder.feed(b"d1:bi01e1:a").unwrap(), Progress::NeedMore);
+        let done = decoder.feed(b"i2e1:bi3ee").unwrap();
+        assert_eq!(
+            done,
+            Progress::Complete(Bencode::Dict(BTreeMap::from([
+                (b"a".to_vec(), Bencode::Int(2)),
+                (b"b".to_vec(), Bencode::Int(1)),
+            ])))
+        );
+        let offsets: Vec<_> = decoder.warnings().iter().map(|w| w.offset).collect();
+        assert_eq!(offsets, vec![4, 8, 14]);
+    }
+}
@@ -0,0 +1,87 @@
+mod limits_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::bencode_ref::BencodeRef;
+    use bit_tor::decode::DecodeOptions;
+    use bit_tor::error::BencodeErrorKind;
+    use bit_tor::incremental::IncrementalDecoder;
+
+    #[test]
+    fn default_depth_stops_stack_overflow() {
+        let mut s = vec![b'l'; 100_000];
+        s.extend(vec![b'e'; 100_000]);
+        let err = Bencode::decode_all(&s).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TooDeep);
+        assert_eq!(err.offset, 256);
+    }
+
+    #[test]
+    fn max_depth() {
+        let options = DecodeOptions {
+            max_depth: 2,
+            ..Default::default()
+        };
+        assert!(BencodeRef::decode_with_options(b"ld1:ai1eee", options).is_ok());
+        let err = BencodeRef::decode_with_options(b"ld1:aleee", options).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TooDeep);
+        assert_eq!(err.path.to_string(), "[0].a");
+    }
+
+    #[test]
+    fn max_string_len_checked_before_data() {
+        let options = DecodeOptions {
+            max_string_len: 4,
+            ..Default::default()
+        };
+        assert!(Bencode::decode_with_options(b"4:spam", options).is_ok());
+        let err = Bencode::decode_with_options(b"l99999999999999:e", options).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::StringTooLong(99999999999999));
+        assert_eq!(err.offset, 1);
+    }
+
+    #[test]
+    fn max_items_and_input_len() {
+        let options = DecodeOptions {
+            max_items: 3,
+            ..Default::default()
+        };
+        assert!(BencodeRef::decode_with_options(b"li1ei2ee", options).is_ok());
+        let err = BencodeRef::decode_with_options(b"li1ei2ei3ee", options).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TooManyItems);
+        let options = DecodeOptions {
+            max_input_len: 4,
+            ..Default::default()
+        };
+        let err = BencodeRef::decode_with_options(b"i100e", options).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::InputTooLarge(5));
+    }
+
+    #[test]
+    fn incremental_limits() {
+        let mut decoder = IncrementalDecoder::with_options(DecodeOptions {
+            max_depth: 1,
+            max_string_len: 3,
+            ..Default::default()
+        });
+        assert_eq!(
+            decoder.feed(b"l1000:").unwrap_err().kind,
+            BencodeErrorKind::StringTooLong(1000)
+        );
+        let mut decoder = IncrementalDecoder::with_options(DecodeOptions {
+            max_depth: 1,
+            ..Default::default()
+        });
+        assert_eq!(
+            decoder.feed(b"ll").unwrap_err().kind,
+            BencodeErrorKind::TooDeep
+        );
+        let mut decoder = IncrementalDecoder::with_options(DecodeOptions {
+            max_input_len: 8,
+            ..Default::default()
+        });
+        decoder.feed(b"l4:sp").unwrap();
+        assert_eq!(
+            decoder.feed(b"amee").unwrap_err().kind,
+            BencodeErrorKind::InputTooLarge(9)
+        );
+    }
+}
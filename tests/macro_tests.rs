@@ -0,0 +1,85 @@
+mod macro_tests {
+    use bit_tor::bencode;
+    use bit_tor::bencode::Bencode;
+    use std::collections::BTreeMap;
+    #[cfg(feature = "std")]
+    use std::collections::HashMap;
+
+    #[test]
+    fn dictionary_matches_hand_built() {
+        let by_hand = Bencode::Dict(BTreeMap::from([
+            (
+                b"announce".to_vec(),
+                Bencode::Message(b"http://tracker.example/announce".to_vec()),
+            ),
+            (
+                b"info".to_vec(),
+                Bencode::Dict(BTreeMap::from([
+                    (b"length".to_vec(), Bencode::Int(42)),
+                    (b"pieces".to_vec(), Bencode::Message(vec![0; 20])),
+                ])),
+            ),
+        ]));
+        let built = bencode! {
+            "announce" => "http://tracker.example/announce",
+            "info" => { "length" => 42, "pieces" => &[0u8; 20] },
+        };
+        assert_eq!(built, by_hand);
+        assert_eq!(bencode!({ "a" => 1 }), bencode!("a" => 1));
+    }
+
+    #[test]
+    fn nested_lists_and_expressions() {
+        let name = String::from("b.txt");
+        let len = 7u64;
+        let built = bencode!({
+            "files" => [
+                { "length" => len * 2, "path" => ["a", name.as_str()] },
+                { "length" => -1, "path" => [] },
+            ],
+            b"raw\xff" => [[1, 2], {}],
+        });
+        assert_eq!(
+            built.encode_val(),
+            b"d5:filesld6:lengthi14e4:pathl1:a5:b.txteed6:lengthi-1e4:pathleee\
+              4:raw\xfflli1ei2eedeee"
+        );
+    }
+
+    #[test]
+    fn scalars_and_empty() {
+        assert_eq!(bencode!(3), Bencode::Int(3));
+        assert_eq!(bencode!("x"), Bencode::Message(b"x".to_vec()));
+        assert_eq!(bencode!([]), Bencode::List(vec![]));
+        assert_eq!(bencode!({}), Bencode::Dict(BTreeMap::new()));
+        assert_eq!(bencode! {}, Bencode::Dict(BTreeMap::new()));
+    }
+
+    #[test]
+    fn from_impls() {
+        assert_eq!(
+            Bencode::from(b"ab".as_slice()),
+            Bencode::Message(b"ab".to_vec())
+        );
+        assert_eq!(Bencode::from(String::from("ab")), Bencode::from("ab"));
+        assert_eq!(Bencode::from(u8::MAX), Bencode::Int(255));
+        assert_eq!(
+            Bencode::from(u64::MAX),
+            Bencode::BigInt("18446744073709551615".into())
+        );
+        assert_eq!(
+            Bencode::from(vec![1u8, 2]),
+            Bencode::List(vec![Bencode::Int(1), Bencode::Int(2)])
+        );
+        assert_eq!(Bencode::from(vec!["a", "b"]).encode_val(), b"l1:a1:be");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn maps_convert_to_dictionaries() {
+        let hashed = HashMap::from([("b", 2), ("a", 1)]);
+        assert_eq!(Bencode::from(hashed).encode_val(), b"d1:ai1e1:bi2ee");
+        let sorted = BTreeMap::from([(String::from("k"), vec![Bencode::from("v")])]);
+        assert_eq!(Bencode::from(sorted).encode_val(), b"d1:kl1:vee");
+    }
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "std")]
+
+mod magnet_tests {
+    use bit_tor::magnet::MagnetLink;
+    use bit_tor::MetaInfo;
+
+    const HASH: &str = "c9e15763f722f23e98a29decdfae341b98d53056";
+    // HASH in base32
+    const HASH_BASE32: &str = "ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW";
+    const V2_HASH: &str = "caf1e1c30e81cb361b9ee167c4aa64228a7fa4fa9f6105232b28ad099f3a302e";
+
+    #[test]
+    fn parses_all_parameters() {
+        let link = format!(
+            "magnet:?xt=urn:btih:{HASH}&dn=Big+Buck%20Bunny&tr=udp%3A%2F%2Fa.example%3A80\
+             &tr.1=http://b.example/announce&ws=http%3A%2F%2Fseed.example%2F\
+             &x.pe=10.0.0.1:6881&so=0,2,4-6&xl=10826029"
+        );
+        let magnet = MagnetLink::parse(&link).unwrap();
+        assert_eq!(hex::encode(magnet.info_hash.unwrap()), HASH);
+        assert_eq!(magnet.info_hash_v2, None);
+        assert_eq!(magnet.display_name.as_deref(), Some("Big Buck Bunny"));
+        assert_eq!(
+            magnet.trackers,
+            ["udp://a.example:80", "http://b.example/announce"]
+        );
+        assert_eq!(magnet.web_seeds, ["http://seed.example/"]);
+        assert_eq!(magnet.peers, ["10.0.0.1:6881"]);
+        assert_eq!(magnet.select_only, Some(vec![0..=0, 2..=2, 4..=6]));
+    }
+
+    #[test]
+    fn plus_is_a_space_only_in_the_name() {
+        let link = format!(
+            "magnet:?xt=urn:btih:{HASH}&dn=a+b&tr=http://t.example/announce?k=a+b\
+             &tr.=http://ignored.example&ws=http://s.example/a+b"
+        );
+        let magnet = MagnetLink::parse(&link).unwrap();
+        assert_eq!(magnet.display_name.as_deref(), Some("a b"));
+        assert_eq!(magnet.trackers, ["http://t.example/announce?k=a+b"]);
+        assert_eq!(magnet.web_seeds, ["http://s.example/a+b"]);
+        let generated = magnet.to_string();
+        assert!(generated.contains("k%3Da%2Bb"), "{generated}");
+        assert_eq!(MagnetLink::parse(&generated).unwrap(), magnet);
+    }
+
+    #[test]
+    fn hash_encodings() {
+        let hex = MagnetLink::parse(&format!("magnet:?xt=urn:btih:{HASH}")).unwrap();
+        let upper = MagnetLink::parse(&format!("magnet:?xt=urn:btih:{}", HASH.to_uppercase()));
+        let base32 = MagnetLink::parse(&format!("magnet:?xt=urn:btih:{HASH_BASE32}")).unwrap();
+        let lower32 = format!("magnet:?xt=urn:btih:{}", HASH_BASE32.to_lowercase());
+        assert_eq!(upper.unwrap(), hex);
+        assert_eq!(base32, hex);
+        assert_eq!(MagnetLink::parse(&lower32).unwrap(), hex);
+        // v2 hashes are SHA-256 multihashes
+        let v2 = MagnetLink::parse(&format!("magnet:?xt=urn:btmh:1220{V2_HASH}")).unwrap();
+        assert_eq!(v2.info_hash, None);
+        assert_eq!(hex::encode(v2.info_hash_v2.unwrap()), V2_HASH);
+        assert_eq!(hex::encode(v2.swarm_hashes()[0]), V2_HASH[..40]);
+    }
+
+    #[test]
+    fn invalid_links() {
+        let invalid = [
+            "http://example.com/?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056".to_string(),
+            "magnet:?dn=no+hash".to_string(),
+            "magnet:?xt=urn:ed2k:31d6cfe0d16ae931b73c59d7e0c089c0".to_string(),
+            format!("magnet:?xt=urn:btih:{}", &HASH[..39]),
+            format!("magnet:?xt=urn:btmh:1114{}", &V2_HASH[..40]),
+            format!("magnet:?xt=urn:btih:{HASH}&xt=urn:btih:{}", "0".repeat(40)),
+            format!("magnet:?xt=urn:btih:{HASH}&so=3-1"),
+            format!("magnet:?xt=urn:btih:{HASH}&dn"),
+        ];
+        for link in invalid {
+            assert!(MagnetLink::parse(&link).is_err(), "{link}");
+        }
+    }
+
+    #[test]
+    fn from_meta_info() {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        let magnet = MagnetLink::from_meta_info(&meta);
+        assert_eq!(magnet.info_hash, Some(meta.info_hash));
+        assert_eq!(magnet.display_name.as_deref(), Some("Big Buck Bunny"));
+        let trackers: usize = meta
+            .announce_list
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(Vec::len)
+            .sum();
+        assert_eq!(magnet.trackers.len(), trackers);
+        let link = magnet.to_string();
+        assert!(link.starts_with(&format!(
+            "magnet:?xt=urn:btih:{}&dn=Big%20Buck%20Bunny&tr=udp%3A%2F%2F",
+            hex::encode(meta.info_hash)
+        )));
+        assert_eq!(MagnetLink::parse(&link).unwrap(), magnet);
+        // A v2 only torrent has just the btmh topic
+        let src = std::fs::read("sample_torrent/bittorrent-v2-test.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        let link = MagnetLink::from_meta_info(&meta).to_string();
+        assert!(link.starts_with(&format!("magnet:?xt=urn:btmh:1220{V2_HASH}&dn=")));
+    }
+
+    #[test]
+    fn round_trip() {
+        let link = format!(
+            "magnet:?xt=urn:btih:{HASH}&xt=urn:btmh:1220{V2_HASH}&dn=a%20b&tr=http%3A%2F%2Fa\
+             &ws=http%3A%2F%2Fs&x.pe=host%3A1&so=1%2C3-4"
+        );
+        let magnet = MagnetLink::parse(&link).unwrap();
+        assert_eq!(magnet.select_only, Some(vec![1..=1, 3..=4]));
+        assert_eq!(magnet.swarm_hashes().len(), 2);
+        let generated = magnet.to_string();
+        assert_eq!(generated, link.replace("%2C", ","));
+        assert_eq!(MagnetLink::parse(&generated).unwrap(), magnet);
+    }
+}
@@ -0,0 +1,88 @@
+mod order_tests {
+    #[cfg(feature = "std")]
+    use bit_tor::bencode::diff;
+    use bit_tor::bencode::{canonicalize, Bencode};
+    use bit_tor::decode::DecodeOptions;
+    use bit_tor::encode::{encoded_len, encoded_len_preserving_order, Encoder};
+    use bit_tor::incremental::{IncrementalDecoder, Progress};
+    #[cfg(feature = "std")]
+    use bit_tor::json::{from_json, to_json};
+
+    const MESSY: &[u8] = b"d1:bi1e1:ald1:zi0e1:yi0eee1:bi2ee";
+
+    fn preserving() -> DecodeOptions {
+        DecodeOptions {
+            lenient: true,
+            preserve_order: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn entries_keep_input_order() {
+        let val = Bencode::decode_with_options(MESSY, preserving()).unwrap();
+        let Bencode::OrderedDict(entries) = &val else {
+            panic!("expected an ordered dict, got {val:?}");
+        };
+        let keys: Vec<&[u8]> = entries.iter().map(|(k, _)| k.as_slice()).collect();
+        assert_eq!(keys, vec![&b"b"[..], b"a", b"b"]);
+        assert_eq!(val.get("b"), Some(&Bencode::Int(1)));
+        assert_eq!(val.pointer("a/0/y"), Some(&Bencode::Int(0)));
+    }
+
+    #[test]
+    fn encoder_modes() {
+        let val = Bencode::decode_with_options(MESSY, preserving()).unwrap();
+        assert_eq!(val.encode_preserving_order(), MESSY);
+        assert_eq!(encoded_len_preserving_order(&val), MESSY.len());
+        let canonical = canonicalize(MESSY).unwrap();
+        assert_eq!(val.encode_val(), canonical);
+        assert_eq!(encoded_len(&val), canonical.len());
+        let mut enc = Encoder::new(Vec::new()).preserve_order(true);
+        enc.encode(&val).unwrap();
+        assert_eq!(enc.into_inner(), MESSY);
+    }
+
+    #[test]
+    fn strict_mode_still_rejects_unsorted() {
+        let options = DecodeOptions {
+            preserve_order: true,
+            ..Default::default()
+        };
+        assert!(Bencode::decode_with_options(MESSY, options).is_err());
+        let sorted = Bencode::decode_with_options(b"d1:ai1e1:bi2ee", options).unwrap();
+        assert!(matches!(sorted, Bencode::OrderedDict(_)));
+    }
+
+    #[test]
+    fn incremental_preserves_order() {
+        let mut decoder = IncrementalDecoder::with_options(preserving());
+        let (first, rest) = MESSY.split_at(9);
+        assert_eq!(decoder.feed(first).unwrap(), Progress::NeedMore);
+        let Progress::Complete(val) = decoder.feed(rest).unwrap() else {
+            panic!("value should be complete");
+        };
+        assert_eq!(val.encode_preserving_order(), MESSY);
+        assert_eq!(decoder.warnings().len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn json_and_diff() {
+        let ordered = Bencode::decode_with_options(MESSY, preserving()).unwrap();
+        let back = from_json(&to_json(&ordered).unwrap()).unwrap();
+        assert_eq!(back.encode_preserving_order(), MESSY);
+        let sorted = Bencode::decode_all(&canonicalize(MESSY).unwrap())
+            .unwrap()
+            .remove(0);
+        assert!(diff(&ordered, &sorted).is_empty());
+    }
+
+    #[test]
+    fn encoded_len_counts_first_duplicate() {
+        for src in [&b"d1:ai1e1:bi2ee"[..], b"d1:bi1e1:ai22e1:bi333ee"] {
+            let val = Bencode::decode_with_options(src, preserving()).unwrap();
+            assert_eq!(encoded_len(&val), val.encode_val().len(), "{src:?}");
+        }
+    }
+}
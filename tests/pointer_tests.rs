@@ -0,0 +1,55 @@
+mod pointer_tests {
+    use bit_tor::bencode::Bencode;
+    use std::collections::BTreeMap;
+
+    fn torrent() -> Bencode {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        Bencode::decode_all(&src).unwrap().remove(0)
+    }
+
+    #[test]
+    fn nested_lookup() {
+        let val = torrent();
+        assert_eq!(val.pointer("info/files/0/length"), Some(&Bencode::Int(140)));
+        assert_eq!(
+            val.pointer("announce-list/1/0"),
+            Some(&Bencode::Message(
+                b"udp://tracker.coppersurfer.tk:6969".to_vec()
+            ))
+        );
+        assert_eq!(
+            val.pointer("/info/files/0/length"),
+            val.pointer("info/files/0/length")
+        );
+    }
+
+    #[test]
+    fn empty_path_is_the_value() {
+        let val = torrent();
+        assert_eq!(val.pointer(""), Some(&val));
+        assert_eq!(val.pointer("/"), Some(&val));
+    }
+
+    #[test]
+    fn missing_or_mismatched() {
+        let val = torrent();
+        assert_eq!(val.pointer("info/nope"), None);
+        assert_eq!(val.pointer("info/files/99"), None);
+        assert_eq!(val.pointer("info/files/first"), None);
+        assert_eq!(val.pointer("announce/0"), None);
+        assert_eq!(val.pointer("info/files/0/length/x"), None);
+    }
+
+    #[test]
+    fn escaped_keys() {
+        let val = Bencode::Dict(BTreeMap::from([
+            (b"a/b".to_vec(), Bencode::Int(1)),
+            (b"c~d".to_vec(), Bencode::Int(2)),
+            (b"7".to_vec(), Bencode::Int(3)),
+        ]));
+        assert_eq!(val.pointer("a~1b"), Some(&Bencode::Int(1)));
+        assert_eq!(val.pointer("c~0d"), Some(&Bencode::Int(2)));
+        // Numeric segments are keys when the value is a dictionary
+        assert_eq!(val.pointer("7"), Some(&Bencode::Int(3)));
+    }
+}
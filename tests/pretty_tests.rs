@@ -0,0 +1,73 @@
+#![cfg(feature = "std")]
+
+mod pretty_tests {
+    use bit_tor::bencode;
+    use bit_tor::bencode::Bencode;
+    use bit_tor::pretty::PrettyOptions;
+
+    #[test]
+    fn nested_layout() {
+        let val = bencode!({
+            "announce" => "http://tracker.example/announce",
+            "info" => { "length" => 5, "name" => "a.txt" },
+            "list" => [1, [], {}],
+        });
+        let expected = r#"{
+  "announce": "http://tracker.example/announce",
+  "info": {
+    "length": 5,
+    "name": "a.txt"
+  },
+  "list": [
+    1,
+    [],
+    {}
+  ]
+}"#;
+        assert_eq!(val.pretty().to_string(), expected);
+    }
+
+    #[test]
+    fn binary_and_long_strings() {
+        let val = bencode!({
+            "peers" => &[0x7f, 0, 0, 1, 0x1a, 0xe1],
+            "pieces" => vec![0xffu8; 200].as_slice(),
+        });
+        let text = val.pretty().to_string();
+        assert!(text.contains(r#""peers": <hex 7f0000011ae1>"#));
+        assert!(text
+            .contains(r#""pieces": <200 bytes, sha1=e007c7d7d8caf4d31e1713d56ad50461f420996f>"#));
+        let full = val.pretty_with(PrettyOptions {
+            max_string_len: usize::MAX,
+            ..Default::default()
+        });
+        assert!(full
+            .to_string()
+            .contains(&format!("<hex {}>", "ff".repeat(200))));
+    }
+
+    #[test]
+    fn escapes_and_binary_keys() {
+        let mut val = bencode!({ "tab\there" => "quote\"d" });
+        val.as_dict_mut().unwrap().insert(
+            vec![0xff],
+            Bencode::BigInt("123456789012345678901234567890".into()),
+        );
+        let expected =
+            "{\n  \"tab\\there\": \"quote\\\"d\",\n  <hex ff>: 123456789012345678901234567890\n}";
+        assert_eq!(val.pretty().to_string(), expected);
+    }
+
+    #[test]
+    fn indent_and_colour() {
+        let val = bencode!({ "a" => [1] });
+        let options = PrettyOptions {
+            indent: 4,
+            colour: true,
+            ..Default::default()
+        };
+        let expected = "{\n    \x1b[34m\"a\"\x1b[0m: [\n        \x1b[33m1\x1b[0m\n    ]\n}";
+        assert_eq!(val.pretty_with(options).to_string(), expected);
+        assert_eq!(Bencode::Int(-3).pretty().to_string(), "-3");
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "std")]
+
+mod reader_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::decode::DecodeOptions;
+    use bit_tor::error::{BencodeError, BencodeErrorKind};
+    use std::io::{self, Cursor};
+
+    const TORRENT: &str = "sample_torrent/big-buck-bunny.torrent";
+
+    fn decode_error(err: &io::Error) -> &BencodeError {
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        err.get_ref().unwrap().downcast_ref().unwrap()
+    }
+
+    #[test]
+    fn path_and_reader_match_bytes() {
+        let src = std::fs::read(TORRENT).unwrap();
+        let expected = Bencode::decode_all(&src).unwrap().remove(0);
+        assert_eq!(Bencode::from_path(TORRENT).unwrap(), expected);
+        assert_eq!(Bencode::from_reader(Cursor::new(&src)).unwrap(), expected);
+        assert_eq!(
+            Bencode::from_reader(std::fs::File::open(TORRENT).unwrap()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn trailing_garbage() {
+        let err = Bencode::from_reader(&b"d1:ai1ee garbage"[..]).unwrap_err();
+        let err = decode_error(&err);
+        assert_eq!(err.kind, BencodeErrorKind::TrailingData);
+        assert_eq!(err.offset, 8);
+        let err = Bencode::from_reader(&b"i1ei2e"[..]).unwrap_err();
+        assert_eq!(decode_error(&err).kind, BencodeErrorKind::TrailingData);
+    }
+
+    #[test]
+    fn reading_stops_past_the_limit() {
+        let options = DecodeOptions {
+            max_input_len: 4,
+            ..Default::default()
+        };
+        // An endless stream still fails, as only max_input_len + 1 bytes are read
+        let endless = io::repeat(b'0');
+        let err = Bencode::from_reader_with_options(endless, options).unwrap_err();
+        assert_eq!(decode_error(&err).kind, BencodeErrorKind::InputTooLarge(5));
+        let val = Bencode::from_reader_with_options(&b"i42e"[..], options).unwrap();
+        assert_eq!(val, Bencode::Int(42));
+    }
+
+    #[test]
+    fn io_errors_pass_through() {
+        let err = Bencode::from_path("sample_torrent/does-not-exist.torrent").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        let err = Bencode::from_reader(&b"d1:a"[..]).unwrap_err();
+        assert_eq!(decode_error(&err).kind, BencodeErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_matches_read() {
+        // The sample torrents are not written to while the tests run
+        let mapped = unsafe { Bencode::from_path_mmap(TORRENT) }.unwrap();
+        assert_eq!(mapped, Bencode::from_path(TORRENT).unwrap());
+    }
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "std")]
+
+mod serde_tests {
+    use bit_tor::bencode::{from_bytes, to_bytes};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TrackerResponse {
+        #[serde(rename = "failure reason")]
+        failure_reason: Option<String>,
+        interval: Option<u32>,
+        #[serde(with = "serde_bytes")]
+        peers: Vec<u8>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Info<'a> {
+        name: &'a str,
+        #[serde(rename = "piece length")]
+        piece_length: u64,
+        #[serde(with = "serde_bytes")]
+        pieces: &'a [u8],
+        files: Option<Vec<FileEntry>>,
+        private: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct FileEntry {
+        length: u64,
+        path: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Event {
+        Started,
+        Progress(u8),
+        Stopped { uploaded: u32 },
+    }
+
+    #[test]
+    fn tracker_response_round_trip() {
+        let src: &[u8] = b"d8:intervali1800e5:peers6:\x7f\0\0\x01\x1a\xe1e";
+        let res: TrackerResponse = from_bytes(src).unwrap();
+        assert_eq!(
+            res,
+            TrackerResponse {
+                failure_reason: None,
+                interval: Some(1800),
+                peers: vec![0x7f, 0, 0, 1, 0x1a, 0xe1],
+            }
+        );
+        assert_eq!(to_bytes(&res).unwrap(), src);
+    }
+
+    #[test]
+    fn borrowed_fields_and_sorted_keys() {
+        let info = Info {
+            name: "dir",
+            piece_length: 16384,
+            pieces: &[0xff; 20],
+            files: Some(vec![FileEntry {
+                length: 3,
+                path: vec!["a".into(), "b.txt".into()],
+            }]),
+            private: Some(true),
+        };
+        let encoded = to_bytes(&info).unwrap();
+        assert!(encoded.starts_with(b"d5:filesld6:lengthi3e4:pathl1:a5:b.txteee4:name3:dir"));
+        let decoded: Info = from_bytes(&encoded).unwrap();
+        assert_eq!(decoded, info);
+        let name_at = encoded.windows(5).position(|w| w == b"3:dir").unwrap() + 2;
+        assert!(std::ptr::eq(
+            decoded.name.as_ptr(),
+            encoded[name_at..].as_ptr()
+        ));
+    }
+
+    #[test]
+    fn enums() {
+        for ev in [
+            Event::Started,
+            Event::Progress(7),
+            Event::Stopped { uploaded: 12 },
+        ] {
+            let encoded = to_bytes(&ev).unwrap();
+            assert_eq!(from_bytes::<Event>(&encoded).unwrap(), ev);
+        }
+        assert_eq!(to_bytes(&Event::Started).unwrap(), b"7:Started");
+        assert_eq!(to_bytes(&Event::Progress(7)).unwrap(), b"d8:Progressi7ee");
+    }
+
+    #[test]
+    fn missing_field_and_wrong_type() {
+        assert!(from_bytes::<FileEntry>(b"d6:lengthi3ee").is_err());
+        assert!(from_bytes::<FileEntry>(b"d6:length1:34:pathlee").is_err());
+        assert!(from_bytes::<FileEntry>(b"d6:lengthi-3e4:pathlee").is_err());
+    }
+
+    #[test]
+    fn decode_error_is_reported() {
+        assert!(from_bytes::<FileEntry>(b"d6:lengthi3e").is_err());
+        assert!(to_bytes(&1.5f64).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "std")]
+
+mod span_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::decode::{DecodeOptions, Decoder};
+    use bit_tor::error::KeyPath;
+    use bit_tor::MetaInfo;
+
+    fn options() -> DecodeOptions {
+        DecodeOptions {
+            record_spans: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spans_cover_each_value() {
+        let s = "d1:ali1e3:abce1:bd1:ci-5eee".as_bytes();
+        let mut decoder = Decoder::with_options(s, options());
+        decoder.decode_next().unwrap();
+        let spans = decoder.spans();
+        assert_eq!(spans.get(&KeyPath::root()), Some(0..s.len()));
+        assert_eq!(spans.get(&KeyPath::root().key(b"a")), Some(4..14));
+        assert_eq!(
+            &s[spans.get(&KeyPath::root().key(b"a").index(1)).unwrap()],
+            b"3:abc"
+        );
+        assert_eq!(
+            &s[spans.get(&KeyPath::root().key(b"b")).unwrap()],
+            b"d1:ci-5ee"
+        );
+        assert_eq!(spans.get(&KeyPath::root().key(b"x")), None);
+    }
+
+    #[test]
+    fn spans_off_by_default() {
+        let mut decoder = Decoder::new(b"li1ee");
+        decoder.decode_next().unwrap();
+        assert!(decoder.spans().0.is_empty());
+    }
+
+    #[test]
+    fn info_hash_uses_original_bytes() {
+        // Keys of the info dict are out of order, so re-encoding it would sort them
+        let info = "d4:name1:a6:lengthi3e12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+        let torrent = format!("d8:announce9:localhost4:info{info}e");
+        let meta = MetaInfo::from_bytes(torrent.as_bytes()).unwrap();
+        let expected = sha1_smol::Sha1::from(info.as_bytes()).digest().bytes();
+        assert_eq!(meta.info_hash, expected);
+        let (root, _) = Bencode::decode_lenient(torrent.as_bytes()).unwrap();
+        let reencoded = root.unwrap_dict()[b"info".as_slice()].encode_val();
+        assert_ne!(sha1_smol::Sha1::from(reencoded).digest().bytes(), expected);
+    }
+
+    #[test]
+    fn canonical_torrent_hash_matches_reencoding() {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        let root = Bencode::decode_all(&src).unwrap().remove(0);
+        let info = root.unwrap_dict()[b"info".as_slice()].encode_val();
+        assert_eq!(meta.info_hash, sha1_smol::Sha1::from(info).digest().bytes());
+    }
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "std")]
+
+mod tracker_tests {
+    use bit_tor::bencode;
+    use bit_tor::bencode::Bencode;
+    use bit_tor::tracker::TrackerTiers;
+    use bit_tor::MetaInfo;
+
+    fn torrent_with(announce_list: Option<Vec<Vec<&str>>>) -> MetaInfo {
+        let mut val = bencode!({
+            "announce" => "http://primary.example/announce",
+            "info" => { "length" => 1, "name" => "a", "piece length" => 16384, "pieces" => &[0; 20] },
+        });
+        if let Some(list) = announce_list {
+            let list: Vec<Vec<String>> = list
+                .into_iter()
+                .map(|tier| tier.into_iter().map(String::from).collect())
+                .collect();
+            val.as_dict_mut()
+                .unwrap()
+                .insert(b"announce-list".to_vec(), list.into());
+        }
+        MetaInfo::from_bytes(&val.encode_val()).unwrap()
+    }
+
+    #[test]
+    fn parses_tiers() {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        let tiers = meta.announce_list.unwrap();
+        assert_eq!(tiers.len(), 8);
+        assert_eq!(
+            tiers[1],
+            vec![b"udp://tracker.coppersurfer.tk:6969".to_vec()]
+        );
+        // Empty tiers are dropped
+        let meta = torrent_with(Some(vec![vec![], vec!["http://a"]]));
+        assert_eq!(meta.announce_list, Some(vec![vec![b"http://a".to_vec()]]));
+    }
+
+    #[test]
+    fn announce_list_replaces_announce() {
+        let meta = torrent_with(None);
+        assert_eq!(meta.announce_list, None);
+        let tiers = TrackerTiers::new(&meta).unwrap();
+        assert_eq!(
+            tiers.tiers(),
+            [vec![b"http://primary.example/announce".to_vec()]]
+        );
+        let meta = torrent_with(Some(vec![vec!["http://a", "http://b"], vec!["http://c"]]));
+        let tiers = TrackerTiers::new(&meta).unwrap();
+        assert_eq!(tiers.tiers().len(), 2);
+        // Shuffling only happens within a tier
+        let mut first = tiers.tiers()[0].clone();
+        first.sort();
+        assert_eq!(first, [b"http://a".to_vec(), b"http://b".to_vec()]);
+        assert_eq!(tiers.tiers()[1], [b"http://c".to_vec()]);
+    }
+
+    #[test]
+    fn responder_moves_to_front() {
+        let meta = torrent_with(Some(vec![
+            vec!["http://dead1", "http://dead2", "http://live"],
+            vec!["http://backup"],
+        ]));
+        let mut tiers = TrackerTiers::new(&meta).unwrap();
+        let mut tried = Vec::new();
+        let res = tiers.announce(|url| {
+            tried.push(url.to_vec());
+            match url {
+                b"http://live" => Ok("peers"),
+                _ => Err("no answer"),
+            }
+        });
+        assert_eq!(res, Ok("peers"));
+        assert_eq!(tried.last().unwrap(), b"http://live");
+        assert!(!tried.contains(&b"http://backup".to_vec()));
+        assert_eq!(tiers.tiers()[0][0], b"http://live");
+        // The next announce goes straight to it
+        let mut calls = 0;
+        tiers
+            .announce(|_| {
+                calls += 1;
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn falls_through_tiers() {
+        let meta = torrent_with(Some(vec![
+            vec!["http://dead1", "http://dead2"],
+            vec!["http://backup"],
+        ]));
+        let mut tiers = TrackerTiers::new(&meta).unwrap();
+        let res = tiers.announce(|url| match url {
+            b"http://backup" => Ok(url.to_vec()),
+            _ => Err(()),
+        });
+        assert_eq!(res, Ok(b"http://backup".to_vec()));
+        let mut tried = 0;
+        let res: Result<(), usize> = tiers.announce(|_| {
+            tried += 1;
+            Err(tried)
+        });
+        assert_eq!(res, Err(3));
+    }
+
+    #[test]
+    fn announce_list_without_announce() {
+        let mut val = bencode!({
+            "info" => { "length" => 1, "name" => "a", "piece length" => 16384, "pieces" => &[0; 20] },
+        });
+        let list = Bencode::from(vec![vec!["http://a"], vec!["http://b"]]);
+        val.as_dict_mut()
+            .unwrap()
+            .insert(b"announce-list".to_vec(), list);
+        let mut meta = MetaInfo::from_bytes(&val.encode_val()).unwrap();
+        assert_eq!(meta.announce, None);
+        assert_eq!(TrackerTiers::new(&meta).unwrap().tiers().len(), 2);
+        // Tiers left empty by hand hold no trackers
+        meta.announce_list = Some(vec![vec![]]);
+        assert!(TrackerTiers::new(&meta).is_none());
+        meta.announce = Some(b"http://c".to_vec());
+        let tiers = TrackerTiers::new(&meta).unwrap();
+        assert_eq!(tiers.tiers(), [vec![b"http://c".to_vec()]]);
+    }
+}
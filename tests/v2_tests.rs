@@ -0,0 +1,106 @@
+#![cfg(feature = "std")]
+
+mod v2_tests {
+    use bit_tor::bencode;
+    use bit_tor::bencode::Bencode;
+    use bit_tor::tracker::TrackerTiers;
+    use bit_tor::MetaInfo;
+
+    const V2_HASH: &str = "caf1e1c30e81cb361b9ee167c4aa64228a7fa4fa9f6105232b28ad099f3a302e";
+
+    // Single file v2 torrent, one piece long unless a bigger length is passed in
+    fn single_file(length: i64, piece_length: i64) -> Bencode {
+        bencode!({
+            "info" => {
+                "file tree" => { "a.txt" => { "" => { "length" => length, "pieces root" => &[7; 32] } } },
+                "meta version" => 2,
+                "name" => "a.txt",
+                "piece length" => piece_length,
+            },
+            "piece layers" => {},
+        })
+    }
+
+    fn parse(val: &Bencode) -> std::io::Result<MetaInfo> {
+        MetaInfo::from_bytes(&val.encode_val())
+    }
+
+    #[test]
+    fn sample_v2_torrent() {
+        let src = std::fs::read("sample_torrent/bittorrent-v2-test.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        assert_eq!(meta.announce, None);
+        assert!(TrackerTiers::new(&meta).is_none());
+        assert_eq!(meta.info.meta_version, 2);
+        assert!(meta.info.pieces.is_none());
+        let tree = meta.info.file_tree.as_ref().unwrap();
+        assert_eq!(tree.len(), 11);
+        let readme = tree
+            .iter()
+            .find(|f| f.path == [b"readme.txt".to_vec()])
+            .unwrap();
+        assert_eq!(readme.length, 61);
+        assert_eq!(
+            hex::encode(readme.pieces_root.unwrap()),
+            "3b3c50a12e27a6b3421b817afd49dfa0d54f69a086cf9914164a9f516e7416e4"
+        );
+        assert_eq!(meta.piece_layers.as_ref().unwrap().len(), 10);
+        assert_eq!(meta.info.files.as_ref().unwrap().len(), 11);
+        assert!(!meta.info.single_file);
+    }
+
+    #[test]
+    fn info_hashes() {
+        let src = std::fs::read("sample_torrent/bittorrent-v2-test.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        assert_eq!(hex::encode(meta.info_hash_v2.unwrap()), V2_HASH);
+        // A v2 only torrent goes by the truncated hash on the wire
+        let truncated = meta.truncated_info_hash_v2().unwrap();
+        assert_eq!(hex::encode(truncated), V2_HASH[..40]);
+        assert_eq!(meta.info_hash, truncated);
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let meta = MetaInfo::from_bytes(&src).unwrap();
+        assert_eq!(meta.info_hash_v2, None);
+        assert_eq!(meta.truncated_info_hash_v2(), None);
+        assert!(meta.info.file_tree.is_none() && meta.piece_layers.is_none());
+    }
+
+    #[test]
+    fn single_file_layout() {
+        let meta = parse(&single_file(100, 16384)).unwrap();
+        assert!(meta.info.single_file);
+        assert_eq!(meta.info.file_length, Some(100));
+        assert_eq!(meta.info.name, b"a.txt");
+        assert!(meta.piece_layers.unwrap().is_empty());
+    }
+
+    #[test]
+    fn piece_layers_are_checked() {
+        // Three pieces need a layer of three hashes
+        let mut val = single_file(40000, 16384);
+        assert!(parse(&val).is_err());
+        let layers = val.get_mut("piece layers").unwrap().as_dict_mut().unwrap();
+        layers.insert(vec![7; 32], Bencode::Message(vec![0; 64]));
+        assert!(parse(&val).is_err());
+        let layers = val.get_mut("piece layers").unwrap().as_dict_mut().unwrap();
+        layers.insert(vec![7; 32], Bencode::Message(vec![0; 96]));
+        assert_eq!(
+            parse(&val).unwrap().piece_layers.unwrap()[&[7; 32]].len(),
+            3
+        );
+    }
+
+    #[test]
+    fn invalid_v2_info() {
+        assert!(parse(&single_file(100, 10000)).is_err());
+        let mut val = single_file(100, 16384);
+        let info = val.get_mut("info").unwrap();
+        *info.get_mut("meta version").unwrap() = Bencode::Int(3);
+        assert!(parse(&val).is_err());
+        let mut val = single_file(100, 16384);
+        let tree = val.get_mut("info").unwrap().get_mut("file tree").unwrap();
+        let file = tree.get_mut("a.txt").unwrap().get_mut("").unwrap();
+        *file.get_mut("pieces root").unwrap() = Bencode::Message(vec![7; 20]);
+        assert!(parse(&val).is_err());
+    }
+}
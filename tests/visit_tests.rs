@@ -0,0 +1,164 @@
+mod visit_tests {
+    use bit_tor::bencode::Bencode;
+    use bit_tor::decode::{DecodeOptions, Decoder};
+    use bit_tor::error::BencodeErrorKind;
+    use bit_tor::visit::{visit, visit_with_options, Visitor};
+
+    // Writes every event down as a short string
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl<'a> Visitor<'a> for Recorder {
+        fn on_int(&mut self, i: i64) {
+            self.0.push(format!("int {i}"));
+        }
+        fn on_big_int(&mut self, digits: &'a str) {
+            self.0.push(format!("big {digits}"));
+        }
+        fn on_bytes(&mut self, bytes: &'a [u8]) {
+            self.0
+                .push(format!("bytes {}", String::from_utf8_lossy(bytes)));
+        }
+        fn on_list_start(&mut self) {
+            self.0.push("[".into());
+        }
+        fn on_list_end(&mut self) {
+            self.0.push("]".into());
+        }
+        fn on_dict_start(&mut self) {
+            self.0.push("{".into());
+        }
+        fn on_key(&mut self, key: &'a [u8]) {
+            self.0.push(format!("key {}", String::from_utf8_lossy(key)));
+        }
+        fn on_dict_end(&mut self) {
+            self.0.push("}".into());
+        }
+    }
+
+    // Sums file lengths without building the tree: only `length` keys one level below
+    // the `files` list count
+    #[derive(Default)]
+    struct FileStats {
+        depth: usize,
+        in_files: bool,
+        files_depth: usize,
+        last_key: Vec<u8>,
+        count: usize,
+        total: i64,
+    }
+
+    impl<'a> Visitor<'a> for FileStats {
+        fn on_list_start(&mut self) {
+            self.depth += 1;
+            if self.last_key == b"files" {
+                self.in_files = true;
+                self.files_depth = self.depth;
+            }
+        }
+        fn on_list_end(&mut self) {
+            if self.in_files && self.depth == self.files_depth {
+                self.in_files = false;
+            }
+            self.depth -= 1;
+        }
+        fn on_dict_start(&mut self) {
+            self.depth += 1;
+            if self.in_files && self.depth == self.files_depth + 1 {
+                self.count += 1;
+            }
+        }
+        fn on_dict_end(&mut self) {
+            self.depth -= 1;
+        }
+        fn on_key(&mut self, key: &'a [u8]) {
+            self.last_key = key.to_vec();
+        }
+        fn on_int(&mut self, i: i64) {
+            if self.in_files && self.depth == self.files_depth + 1 && self.last_key == b"length" {
+                self.total += i;
+            }
+        }
+    }
+
+    #[test]
+    fn event_order() {
+        let mut rec = Recorder::default();
+        visit(b"d1:ali1e2:xye1:bdee", &mut rec).unwrap();
+        assert_eq!(
+            rec.0,
+            vec!["{", "key a", "[", "int 1", "bytes xy", "]", "key b", "{", "}", "}"]
+        );
+    }
+
+    #[test]
+    fn file_statistics_match_tree() {
+        let src = std::fs::read("sample_torrent/big-buck-bunny.torrent").unwrap();
+        let mut stats = FileStats::default();
+        visit(&src, &mut stats).unwrap();
+        let tree = Bencode::decode_all(&src).unwrap().remove(0);
+        let files = tree
+            .pointer("info/files")
+            .and_then(Bencode::as_list)
+            .unwrap();
+        assert_eq!(stats.count, files.len());
+        let total: i64 = files
+            .iter()
+            .map(|f| f.get("length").and_then(Bencode::as_int).unwrap())
+            .sum();
+        assert_eq!(stats.total, total);
+    }
+
+    #[test]
+    fn errors_match_decoder() {
+        for src in [
+            &b"d1:bi1e1:ai2ee"[..],
+            b"d1:ai1e1:ai2ee",
+            b"li03ee",
+            b"d1:ae",
+            b"i1ei2e",
+        ] {
+            let expected = Bencode::decode_with_options(src, DecodeOptions::default()).unwrap_err();
+            let err = visit(src, &mut Recorder::default()).unwrap_err();
+            assert_eq!(err, expected);
+        }
+    }
+
+    #[test]
+    fn options_apply() {
+        let options = DecodeOptions {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let err = visit_with_options(b"lli1eee", &mut Recorder::default(), options).unwrap_err();
+        assert_eq!(err.kind, BencodeErrorKind::TooDeep);
+        let options = DecodeOptions {
+            big_ints: true,
+            ..Default::default()
+        };
+        let mut rec = Recorder::default();
+        visit_with_options(b"i99999999999999999999e", &mut rec, options).unwrap();
+        assert_eq!(rec.0, vec!["big 99999999999999999999"]);
+    }
+
+    #[test]
+    fn lenient_reports_every_entry() {
+        let options = DecodeOptions {
+            lenient: true,
+            ..Default::default()
+        };
+        let src = b"d1:bi1e1:ai2e1:bi3ee";
+        let mut decoder = Decoder::with_options(src, options);
+        let mut rec = Recorder::default();
+        decoder.visit_next(&mut rec).unwrap();
+        assert_eq!(rec.0.len(), 8);
+        let kinds: Vec<_> = decoder.warnings().iter().map(|w| w.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BencodeErrorKind::UnsortedKeys(b"a".to_vec()),
+                BencodeErrorKind::DuplicateKey(b"b".to_vec()),
+            ]
+        );
+    }
+}